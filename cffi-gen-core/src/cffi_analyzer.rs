@@ -0,0 +1,463 @@
+use crate::cffi_error::*;
+use crate::defines::*;
+use quote::ToTokens;
+use std::path::PathBuf;
+use syn::{
+    Attribute, Expr, GenericArgument, Ident, LitStr, Meta, Path, PathArguments, Token, Type,
+    TypeArray, TypeImplTrait, TypeParamBound, TypePath, TypeReference, punctuated::Punctuated,
+};
+
+// =====================================================================
+// メインの解析用構造体
+// =====================================================================
+pub struct CFFIAnalyzer;
+
+impl CFFIAnalyzer {
+    // =====================================================================
+    // 指定引数が可変参照型の場合、その型をTypeで返す関数
+    // =====================================================================
+    pub fn extract_mut(ty: &Type) -> Option<&Type> {
+        if let Type::Reference(TypeReference {
+            elem,
+            mutability: Some(_),
+            ..
+        }) = ty
+        {
+            return Some(elem);
+        }
+
+        None
+    }
+
+    // =====================================================================
+    // 指定引数をOption<Path>で返す関数
+    // =====================================================================
+    pub fn extract_path(ty: &Type) -> Option<&Path> {
+        if let Type::Path(type_path) = ty {
+            return Some(&type_path.path);
+        }
+        None
+    }
+
+    // =====================================================================
+    // アトリビュートマクロの引数(`lib = "x", as_result`等)を
+    // config{}内と同じ形式のアトリビュート群に変換する関数
+    // =====================================================================
+    pub fn metas_to_attrs(metas: impl IntoIterator<Item = Meta>) -> Vec<Attribute> {
+        metas
+            .into_iter()
+            .map(|mut meta| {
+                let path = match &mut meta {
+                    Meta::Path(path)
+                    | Meta::List(syn::MetaList { path, .. })
+                    | Meta::NameValue(syn::MetaNameValue { path, .. }) => path,
+                };
+                // lib は library_name の省略形
+                if let Some(ident) = path.get_ident()
+                    && ident == M_ATTR_LIBRARY_NAME_SHORT
+                {
+                    *path = Ident::new(M_ATTR_LIBRARY_NAME, ident.span()).into();
+                }
+                syn::parse_quote!(#[#meta])
+            })
+            .collect()
+    }
+
+    // =====================================================================
+    // 指定アトリビュート群から#[cffi]を取り除き、その引数をアトリビュート群として返す関数
+    // (#[cffi]が存在しない場合はNone)
+    // =====================================================================
+    pub fn take_cffi_attr(attrs: &mut Vec<Attribute>) -> syn::Result<Option<Vec<Attribute>>> {
+        let Some(pos) = attrs
+            .iter()
+            .position(|attr| attr.path().is_ident(M_ATTR_CFFI))
+        else {
+            return Ok(None);
+        };
+        let attr = attrs.remove(pos);
+        let metas = match &attr.meta {
+            Meta::Path(_) => Punctuated::new(),
+            Meta::List(list) => {
+                list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?
+            }
+            Meta::NameValue(nv) => {
+                return Err(CFFIErrorKind::InvalidCFFIAttribute.spanned(nv));
+            }
+        };
+        Ok(Some(Self::metas_to_attrs(metas)))
+    }
+
+    // =====================================================================
+    // 指定引数が不変参照型の場合、その型をTypeで返す関数
+    // =====================================================================
+    pub fn extract_ref(ty: &Type) -> Option<&Type> {
+        if let Type::Reference(TypeReference {
+            elem,
+            mutability: None,
+            ..
+        }) = ty
+        {
+            return Some(elem);
+        }
+
+        None
+    }
+
+    // =====================================================================
+    // 指定アトリビュートが存在するかをboolで返す関数
+    // =====================================================================
+    pub fn has_path_attr(attrs: &[syn::Attribute], ident: &str) -> bool {
+        attrs.iter().any(|attr| attr.path().is_ident(ident))
+    }
+
+    // =====================================================================
+    // 呼び出し規約(ABI)が対応しているものかを検証する関数
+    // =====================================================================
+    pub fn validate_abi(abi: &LitStr) -> syn::Result<()> {
+        if SUPPORTED_ABIS.contains(&abi.value().as_str()) {
+            return Ok(());
+        }
+        let expected = SUPPORTED_ABIS
+            .iter()
+            .map(|abi| format!("\"{}\"", abi))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Err(CFFIErrorKind::UnknownAbi(abi.value(), expected).at(abi.span()))
+    }
+
+    // =====================================================================
+    // 指定型がポインタ型かboolで返す関数
+    // =====================================================================
+    pub fn is_pointer(ty: &Type) -> bool {
+        matches!(ty, Type::Ptr(_))
+    }
+
+    // =====================================================================
+    // 指定型が符号付き整数型(c_int等の別名を含む)かboolで返す関数
+    // =====================================================================
+    pub fn is_signed_integer(ty: &Type) -> bool {
+        Self::extract_path(ty)
+            .and_then(|path| path.segments.last())
+            .is_some_and(|segment| {
+                SIGNED_INTEGER_TYPES.contains(&segment.ident.to_string().as_str())
+            })
+    }
+
+    // =====================================================================
+    // 指定型がCopyを実装していると分かる型かboolで返す関数
+    // (プリミティブ型、不変参照、ポインタ、関数ポインタ、それらのOption、配列、タプル)
+    // =====================================================================
+    pub fn is_copy(ty: &Type) -> bool {
+        match ty {
+            Type::Reference(TypeReference {
+                mutability: None, ..
+            })
+            | Type::Ptr(_)
+            | Type::BareFn(_) => true,
+            Type::Array(TypeArray { elem, .. }) | Type::Paren(syn::TypeParen { elem, .. }) => {
+                Self::is_copy(elem)
+            }
+            Type::Tuple(tuple) => tuple.elems.iter().all(Self::is_copy),
+            _ => match Self::is_option(ty) {
+                Some(inner_ty) => Self::is_copy(inner_ty),
+                None => {
+                    Self::is_signed_integer(ty)
+                        || Self::extract_path(ty)
+                            .and_then(|path| path.segments.last())
+                            .is_some_and(|segment| {
+                                segment.arguments.is_none()
+                                    && COPY_PRIMITIVE_TYPES
+                                        .contains(&segment.ident.to_string().as_str())
+                            })
+                }
+            },
+        }
+    }
+
+    // =====================================================================
+    // 引数がimplのトレイト型かどうかをboolで返す関数
+    // =====================================================================
+    pub fn is_impl(ty: &Type) -> bool {
+        matches!(ty, Type::ImplTrait(_))
+    }
+
+    // =====================================================================
+    // トレイト型の場合に名前を比較してboolで返す関数
+    // =====================================================================
+    pub fn is_impl_trait_named(ty: &Type, target: &str) -> bool {
+        match ty {
+            Type::ImplTrait(it) => it.bounds.iter().any(
+                |bound| matches!(bound, TypeParamBound::Trait(tb) if tb.path.segments.last().is_some_and(|segment| segment.ident == target)),
+            ),
+            _ => false,
+        }
+    }
+
+    pub fn is_impl_to_string(ty: &Type) -> bool {
+        Self::is_impl_trait_named(ty, "ToString")
+    }
+
+    pub fn is_impl_display(ty: &Type) -> bool {
+        Self::is_impl_trait_named(ty, "Display")
+    }
+
+    // =====================================================================
+    // Option<T>かどうかを判定
+    // =====================================================================
+    pub fn is_option(ty: &Type) -> Option<&Type> {
+        if let Type::Path(TypePath { path, .. }) = ty
+            && path.segments.len() == 1
+            && path.segments[0].ident == "Option"
+            && let PathArguments::AngleBracketed(args) = &path.segments[0].arguments
+            && let Some(GenericArgument::Type(inner_ty)) = args.args.first()
+        {
+            return Some(inner_ty);
+        }
+        None
+    }
+
+    // =====================================================================
+    // AsRef<T>を取り出して返す
+    // =====================================================================
+    pub fn extract_as_ref_generic(ty: &Type) -> Option<&Type> {
+        // 参照型(&T, &mut T)の場合
+        if let Type::Reference(ref_type) = ty {
+            return Self::extract_as_ref_generic(&ref_type.elem);
+        }
+
+        // 通常のimpl AsRef<T>の場合
+        if let Type::ImplTrait(TypeImplTrait { bounds, .. }) = ty {
+            for bound in bounds {
+                if let TypeParamBound::Trait(trait_bound) = bound {
+                    let segments = &trait_bound.path.segments;
+                    if let Some(segment) = segments.last()
+                        && segment.ident == "AsRef"
+                        && let PathArguments::AngleBracketed(args) = &segment.arguments
+                    {
+                        for arg in &args.args {
+                            if let GenericArgument::Type(inner_ty) = arg {
+                                return Some(inner_ty);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn extract_as_mut_generic(ty: &Type) -> Option<&Type> {
+        // 参照型(&T, &mut T)の場合
+        if let Type::Reference(ref_type) = ty {
+            return Self::extract_as_mut_generic(&ref_type.elem);
+        }
+
+        // 通常のimpl AsMut<T>の場合
+        if let Type::ImplTrait(TypeImplTrait { bounds, .. }) = ty {
+            for bound in bounds {
+                if let TypeParamBound::Trait(trait_bound) = bound {
+                    let segments = &trait_bound.path.segments;
+                    if let Some(segment) = segments.last()
+                        && segment.ident == "AsMut"
+                        && let PathArguments::AngleBracketed(args) = &segment.arguments
+                    {
+                        for arg in &args.args {
+                            if let GenericArgument::Type(inner_ty) = arg {
+                                return Some(inner_ty);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // =====================================================================
+    // impl Into<T>のTを取り出して返す(参照の場合は所有権を渡せないため対象外)
+    // =====================================================================
+    pub fn extract_impl_into_generic(ty: &Type) -> Option<&Type> {
+        let Type::ImplTrait(TypeImplTrait { bounds, .. }) = ty else {
+            return None;
+        };
+        bounds.iter().find_map(|bound| match bound {
+            TypeParamBound::Trait(trait_bound) => {
+                let segment = trait_bound.path.segments.last()?;
+                match &segment.arguments {
+                    PathArguments::AngleBracketed(args) if segment.ident == "Into" => {
+                        args.args.iter().find_map(|arg| match arg {
+                            GenericArgument::Type(inner_ty) => Some(inner_ty),
+                            _ => None,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+    }
+
+    // =====================================================================
+    // strかどうかを判定
+    // =====================================================================
+    pub fn is_str(ty: &Type) -> bool {
+        matches!(ty, Type::Path(TypePath { qself: None, path }) if path.is_ident("str"))
+    }
+
+    pub fn extract_vec_inner_type(ty: &Type) -> Option<&Type> {
+        if let Type::Path(type_path) = ty
+            && let Some(last_segment) = type_path.path.segments.last()
+            && last_segment.ident == "Vec"
+            && let PathArguments::AngleBracketed(args) = &last_segment.arguments
+        {
+            for arg in &args.args {
+                if let GenericArgument::Type(inner_ty) = arg {
+                    return Some(inner_ty);
+                }
+            }
+        }
+        // &mut Vec<T> などの参照タイプも対応
+        if let Type::Reference(TypeReference { elem, .. }) = ty {
+            return Self::extract_vec_inner_type(elem);
+        }
+        None
+    }
+
+    pub fn to_pascal_case(s: &str) -> String {
+        s.split('_')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let mut c = part.chars();
+                match c.next() {
+                    None => String::new(),
+                    Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
+                }
+            })
+            .collect()
+    }
+
+    // =====================================================================
+    // 名前を単語に分割する関数
+    // (`_`を含む場合は`_`区切りの各部分を1単語とし、部分内では分割しない)
+    // (`_`を含まない場合は小文字/数字から大文字への切り替わり、"HTTPServer"の様な略語の終わりで分割する)
+    // "ChangeWindowMode" -> [Change, Window, Mode]、"DxLib_Init" -> [DxLib, Init]
+    // =====================================================================
+    pub fn split_words(s: &str) -> Vec<String> {
+        let parts = s.split('_').filter(|part| !part.is_empty());
+        if s.contains('_') {
+            return parts.map(str::to_string).collect();
+        }
+        let mut words = Vec::new();
+        for part in parts {
+            let chars = part.chars().collect::<Vec<_>>();
+            let mut word = String::new();
+            for (i, &c) in chars.iter().enumerate() {
+                let prev = i.checked_sub(1).map(|i| chars[i]);
+                let next = chars.get(i + 1);
+                let boundary = c.is_uppercase()
+                    && match prev {
+                        Some(prev) if prev.is_lowercase() || prev.is_ascii_digit() => true,
+                        Some(prev) if prev.is_uppercase() => next.is_some_and(|n| n.is_lowercase()),
+                        _ => false,
+                    };
+                if boundary && !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                word.push(c);
+            }
+            if !word.is_empty() {
+                words.push(word);
+            }
+        }
+        words
+    }
+
+    // =====================================================================
+    // 名前を指定の命名規則(#[rename_all = value])に変換する関数
+    // (未知の命名規則の場合はNone)
+    // =====================================================================
+    pub fn apply_rename_rule(s: &str, rule: &str) -> Option<String> {
+        let words = Self::split_words(s);
+        let capitalize = |word: &String| {
+            let mut c = word.chars();
+            match c.next() {
+                None => String::new(),
+                Some(first) => {
+                    first.to_uppercase().collect::<String>() + &c.as_str().to_lowercase()
+                }
+            }
+        };
+        let renamed = match rule {
+            "snake_case" => words
+                .iter()
+                .map(|w| w.to_lowercase())
+                .collect::<Vec<_>>()
+                .join("_"),
+            "SCREAMING_SNAKE_CASE" => words
+                .iter()
+                .map(|w| w.to_uppercase())
+                .collect::<Vec<_>>()
+                .join("_"),
+            "PascalCase" => words.iter().map(capitalize).collect(),
+            "camelCase" => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| {
+                    if i == 0 {
+                        w.to_lowercase()
+                    } else {
+                        capitalize(w)
+                    }
+                })
+                .collect(),
+            "lowercase" => words.concat().to_lowercase(),
+            "UPPERCASE" => words.concat().to_uppercase(),
+            _ => return None,
+        };
+        Some(renamed)
+    }
+
+    // =====================================================================
+    // トークン列を読みやすい文字列にする関数(`& mut impl AsMut < str >` -> `&mut impl AsMut<str>`等)
+    // (型、式をそれぞれ項目に埋め込んでprettypleaseで整形し、改行は空白にまとめる)
+    // =====================================================================
+    pub fn tokens_to_string(tokens: impl ToTokens) -> String {
+        let tokens = tokens.into_token_stream();
+        let (item, prefix, suffix): (syn::Item, _, _) =
+            if let Ok(ty) = syn::parse2::<Type>(tokens.clone()) {
+                (syn::parse_quote! { type T = #ty; }, "type T = ", ";")
+            } else if let Ok(expr) = syn::parse2::<Expr>(tokens.clone()) {
+                (
+                    syn::parse_quote! { const C: () = #expr; },
+                    "const C: () = ",
+                    ";",
+                )
+            } else {
+                return tokens.to_string();
+            };
+        let file = syn::File {
+            shebang: None,
+            attrs: Vec::new(),
+            items: vec![item],
+        };
+        let text = prettyplease::unparse(&file)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        text.strip_prefix(prefix)
+            .and_then(|text| text.strip_suffix(suffix))
+            .map(str::to_string)
+            .unwrap_or(text)
+    }
+
+    // =====================================================================
+    // 相対パスをCARGO_MANIFEST_DIRからのパスにする関数(#[def_file]、ヘッダーファイル等)
+    // =====================================================================
+    pub fn manifest_path(path: &str) -> PathBuf {
+        let path = PathBuf::from(path);
+        match std::env::var("CARGO_MANIFEST_DIR") {
+            Ok(manifest_dir) if path.is_relative() => PathBuf::from(manifest_dir).join(path),
+            _ => path,
+        }
+    }
+}
@@ -0,0 +1,385 @@
+use crate::cffi_error::*;
+use crate::defines::*;
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{Attribute, Expr, ExprLit, ExprPath, Ident, Lit, LitStr, Meta, MetaNameValue, Type};
+
+// =====================================================================
+// アトリビュートを使用可能な位置
+// =====================================================================
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CFFIAttributeLevel {
+    // config{}内(#[cffi_module(...)]、単独の#[cffi(...)]の引数を含む)
+    Config,
+    // 関数宣言上部(#[cffi_module]内の#[cffi(...)]の引数を含む)
+    Function,
+    // 関数宣言内の引数前
+    Argument,
+}
+
+impl CFFIAttributeLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            CFFIAttributeLevel::Config => "config",
+            CFFIAttributeLevel::Function => "function",
+            CFFIAttributeLevel::Argument => "argument",
+        }
+    }
+}
+
+use CFFIAttributeLevel::{Argument, Config, Function};
+
+// =====================================================================
+// アトリビュート名と使用可能な位置の一覧
+// =====================================================================
+const ATTRIBUTE_LEVELS: &[(&str, &[CFFIAttributeLevel])] = &[
+    (M_ATTR_LIBRARY_NAME, &[Config]),
+    (M_ATTR_LIBRARY_LINK_TYPE, &[Config]),
+    (M_ATTR_LINK_CFG, &[Config]),
+    (M_ATTR_SYMBOL_RESOLUTION, &[Config]),
+    (M_ATTR_DEF_FILE, &[Config]),
+    (M_ATTR_DEF_REPORT_UNDECLARED, &[Config]),
+    (M_ATTR_ABI, &[Config, Function]),
+    (M_ATTR_AS_RESULT, &[Config, Function]),
+    (M_ATTR_AS_RESULT_ERROR_TYPE, &[Config, Function]),
+    (
+        M_ATTR_AS_RESULT_ERROR_TYPE_TOP_PRIORITY,
+        &[Config, Function],
+    ),
+    (M_ATTR_FUNC_NAME_TOP_PREFIX, &[Config, Function]),
+    (M_ATTR_FUNC_NAME_DOWN_PREFIX, &[Config, Function]),
+    (M_ATTR_ERROR_CONDITION, &[Config, Function]),
+    (M_ATTR_ERROR_WITH_ARGS, &[Config, Function]),
+    (M_ATTR_ERROR_VARIANT, &[Function]),
+    (M_ATTR_ARG_CONVERT, &[Config, Function]),
+    (M_ATTR_FUNC_NAME, &[Function]),
+    (M_ATTR_FUNC_ALIAS, &[Function]),
+    (M_ATTR_FUNC_ALIAS_SHORT, &[Function]),
+    (M_ATTR_NOT_NULL_ASSERT, &[Config, Function]),
+    (M_ATTR_OPTIONAL, &[Function]),
+    (M_ATTR_RENAME_ALL, &[Config, Function]),
+    (M_ATTR_STRIP_PREFIX, &[Config, Function]),
+    (M_ATTR_AS_ARG_TYPE, &[Argument]),
+    (M_ATTR_OPTION_DEFAULT, &[Argument]),
+    (M_ATTR_CALLBACK, &[Argument]),
+];
+
+// 設計のみで未実装のアトリビュート(指定された場合はエラーとする)
+const UNSUPPORTED_ATTRIBUTES: &[&str] = &[M_ATTR_NOT_NULL_ASSERT];
+
+// =====================================================================
+// アトリビュートの値
+// (`name`,`name = value`,`name(value)`のいずれの形式も同じ値として扱う)
+// =====================================================================
+pub enum CFFIAttributeValue {
+    // #[name]
+    None,
+    // #[name = "value"],#[name = 1],#[name(true)]等
+    Lit(Lit),
+    // #[name = default],#[name = MyError::Variant]等
+    Path(ExprPath),
+    // #[name = eq(-1)],#[name(a..b)]等
+    Expr(Expr),
+    // 式として解析できない値(#[name(Box<dyn Error>)]等)
+    Tokens(TokenStream),
+}
+
+// =====================================================================
+// 解析済みのアトリビュート
+// =====================================================================
+pub struct CFFIAttribute {
+    pub value: CFFIAttributeValue,
+    // エラー位置の指定用
+    tokens: TokenStream,
+}
+
+// =====================================================================
+// アトリビュート用解析用構造体
+// =====================================================================
+pub struct CFFIAttributeAnalyzer;
+
+impl CFFIAttributeAnalyzer {
+    // =====================================================================
+    // アトリビュートを名前と値に解析する関数
+    // =====================================================================
+    pub fn parse(attr: &Attribute) -> syn::Result<CFFIAttribute> {
+        let path = attr.path();
+        path.get_ident().ok_or_else(|| {
+            CFFIErrorKind::UnknownAttribute {
+                name: path.to_token_stream().to_string(),
+                suggestion: None,
+            }
+            .spanned(path)
+        })?;
+        let value = match &attr.meta {
+            Meta::Path(_) => CFFIAttributeValue::None,
+            Meta::NameValue(MetaNameValue { value, .. }) => Self::classify(value.clone()),
+            Meta::List(list) => match list.parse_args::<Expr>() {
+                Ok(expr) => Self::classify(expr),
+                Err(_) => CFFIAttributeValue::Tokens(list.tokens.clone()),
+            },
+        };
+        Ok(CFFIAttribute {
+            value,
+            tokens: attr.meta.to_token_stream(),
+        })
+    }
+
+    // =====================================================================
+    // 式を値の種類ごとに分類する関数
+    // =====================================================================
+    fn classify(expr: Expr) -> CFFIAttributeValue {
+        match expr {
+            Expr::Lit(ExprLit { lit, .. }) => CFFIAttributeValue::Lit(lit),
+            Expr::Path(expr_path) => CFFIAttributeValue::Path(expr_path),
+            expr => CFFIAttributeValue::Expr(expr),
+        }
+    }
+
+    // =====================================================================
+    // 指定名のアトリビュートを探して解析する関数(存在しない場合はNone)
+    // =====================================================================
+    pub fn find(attrs: &[Attribute], name: &str) -> syn::Result<Option<CFFIAttribute>> {
+        attrs
+            .iter()
+            .find(|attr| attr.path().is_ident(name))
+            .map(Self::parse)
+            .transpose()
+    }
+
+    // =====================================================================
+    // 指定アトリビュートの値を文字列として取得する関数
+    // (#[name = "value"]の他、#[name = value]の識別子も文字列として扱う)
+    // =====================================================================
+    pub fn get_str(attrs: &[Attribute], name: &'static str) -> syn::Result<Option<LitStr>> {
+        let Some(attr) = Self::find(attrs, name)? else {
+            return Ok(None);
+        };
+        match &attr.value {
+            CFFIAttributeValue::Lit(Lit::Str(lit_str)) => Ok(Some(lit_str.clone())),
+            CFFIAttributeValue::Path(expr_path) if expr_path.path.get_ident().is_some() => {
+                Ok(expr_path
+                    .path
+                    .get_ident()
+                    .map(|ident| LitStr::new(&ident.to_string(), ident.span())))
+            }
+            CFFIAttributeValue::None => Err(attr.missing_value(name)),
+            _ => Err(attr.invalid_value(name, "a string literal")),
+        }
+    }
+
+    // =====================================================================
+    // 指定アトリビュートの値をboolとして取得する関数
+    // (#[name],#[name = true],#[name = default]はtrue、#[name = false]はfalse)
+    // =====================================================================
+    pub fn get_bool(attrs: &[Attribute], name: &'static str) -> syn::Result<Option<bool>> {
+        let Some(attr) = Self::find(attrs, name)? else {
+            return Ok(None);
+        };
+        let value = match &attr.value {
+            CFFIAttributeValue::None => return Ok(Some(true)),
+            CFFIAttributeValue::Lit(Lit::Bool(lit_bool)) => return Ok(Some(lit_bool.value)),
+            CFFIAttributeValue::Lit(Lit::Str(lit_str)) => lit_str.value(),
+            CFFIAttributeValue::Path(expr_path) => expr_path.path.to_token_stream().to_string(),
+            _ => String::new(),
+        };
+        match value.as_str() {
+            "true" | "default" => Ok(Some(true)),
+            "false" => Ok(Some(false)),
+            _ => Err(attr.invalid_value(name, "`true`, `false` or `default`")),
+        }
+    }
+
+    // =====================================================================
+    // 指定アトリビュートの値を式として取得する関数
+    // (文字列リテラルの場合は中身を式として解析する)
+    // =====================================================================
+    pub fn get_expr(attrs: &[Attribute], name: &'static str) -> syn::Result<Option<Expr>> {
+        let Some(attr) = Self::find(attrs, name)? else {
+            return Ok(None);
+        };
+        let expr = match attr.value {
+            CFFIAttributeValue::Lit(Lit::Str(lit_str)) => lit_str.parse::<Expr>().map_err(|e| {
+                CFFIErrorKind::InvalidExpression(name, e.to_string()).at(lit_str.span())
+            })?,
+            CFFIAttributeValue::Lit(lit) => syn::parse_quote! { #lit },
+            CFFIAttributeValue::Path(expr_path) => Expr::Path(expr_path),
+            CFFIAttributeValue::Expr(expr) => expr,
+            CFFIAttributeValue::Tokens(tokens) => {
+                syn::parse2::<Expr>(tokens.clone()).map_err(|e| {
+                    CFFIErrorKind::InvalidExpression(name, e.to_string()).spanned(tokens)
+                })?
+            }
+            CFFIAttributeValue::None => return Err(attr.missing_value(name)),
+        };
+        Ok(Some(expr))
+    }
+
+    // =====================================================================
+    // 指定アトリビュートの値を型として取得する関数
+    // (文字列リテラルの場合は中身を型として解析する)
+    // =====================================================================
+    pub fn get_type(attrs: &[Attribute], name: &'static str) -> syn::Result<Option<Type>> {
+        let Some(attr) = Self::find(attrs, name)? else {
+            return Ok(None);
+        };
+        let ty = match &attr.value {
+            CFFIAttributeValue::Lit(Lit::Str(lit_str)) => lit_str
+                .parse::<Type>()
+                .map_err(|e| CFFIErrorKind::InvalidType(name, e.to_string()).at(lit_str.span()))?,
+            CFFIAttributeValue::Path(expr_path) => {
+                let path = &expr_path.path;
+                syn::parse_quote! { #path }
+            }
+            CFFIAttributeValue::Expr(expr) => Self::parse_type(expr.to_token_stream(), name)?,
+            CFFIAttributeValue::Tokens(tokens) => Self::parse_type(tokens.clone(), name)?,
+            CFFIAttributeValue::Lit(_) => return Err(attr.invalid_value(name, "a type")),
+            CFFIAttributeValue::None => return Err(attr.missing_value(name)),
+        };
+        Ok(Some(ty))
+    }
+
+    // =====================================================================
+    // トークン列を型として解析する関数
+    // =====================================================================
+    fn parse_type(tokens: TokenStream, name: &'static str) -> syn::Result<Type> {
+        syn::parse2::<Type>(tokens.clone())
+            .map_err(|e| CFFIErrorKind::InvalidType(name, e.to_string()).spanned(tokens))
+    }
+
+    // =====================================================================
+    // 指定アトリビュートの値をRustの識別子として取得する関数
+    // =====================================================================
+    pub fn get_ident(attrs: &[Attribute], name: &'static str) -> syn::Result<Option<Ident>> {
+        let Some(attr) = Self::find(attrs, name)? else {
+            return Ok(None);
+        };
+        match &attr.value {
+            CFFIAttributeValue::Lit(Lit::Str(lit_str)) => lit_str
+                .parse::<Ident>()
+                .map(Some)
+                .map_err(|_| CFFIErrorKind::InvalidIdentifier(lit_str.value()).at(lit_str.span())),
+            CFFIAttributeValue::Path(expr_path) if expr_path.path.get_ident().is_some() => {
+                Ok(expr_path.path.get_ident().cloned())
+            }
+            CFFIAttributeValue::None => Err(attr.missing_value(name)),
+            _ => Err(attr.invalid_value(name, "an identifier")),
+        }
+    }
+
+    // =====================================================================
+    // 関数単位の指定を優先し、指定がなければconfigの指定を取得する関数
+    // (getにはget_str,get_bool等を指定する)
+    // =====================================================================
+    pub fn resolve<T>(
+        attrs: &[Attribute],
+        config_attrs: &[Attribute],
+        name: &'static str,
+        get: fn(&[Attribute], &'static str) -> syn::Result<Option<T>>,
+    ) -> syn::Result<Option<T>> {
+        match get(attrs, name)? {
+            Some(value) => Ok(Some(value)),
+            None => get(config_attrs, name),
+        }
+    }
+
+    // =====================================================================
+    // アトリビュート群が指定位置で使用可能かを検証する関数
+    // (未知のアトリビュートには近い名前を提案する)
+    // =====================================================================
+    pub fn validate(attrs: &[Attribute], level: CFFIAttributeLevel) -> Result<(), CFFIError> {
+        let mut errors = CFFIError::new();
+        for attr in attrs {
+            let path = attr.path();
+            let name = path.to_token_stream().to_string();
+            if PASSTHROUGH_ATTRIBUTES.contains(&name.as_str()) {
+                continue;
+            }
+            match ATTRIBUTE_LEVELS.iter().find(|(known, _)| *known == name) {
+                Some(_) if UNSUPPORTED_ATTRIBUTES.contains(&name.as_str()) => {
+                    errors.push(CFFIErrorKind::UnsupportedAttribute(name).spanned(path));
+                }
+                Some((_, levels)) if levels.contains(&level) => {}
+                Some((_, levels)) => {
+                    let allowed = levels
+                        .iter()
+                        .map(|level| level.name())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    errors.push(
+                        CFFIErrorKind::MisplacedAttribute(name, level.name(), allowed)
+                            .spanned(path),
+                    );
+                }
+                None => {
+                    let suggestion = Self::suggest_attribute(&name, level);
+                    errors.push(CFFIErrorKind::UnknownAttribute { name, suggestion }.spanned(path));
+                }
+            }
+        }
+        errors.finish(())
+    }
+
+    // =====================================================================
+    // 未知のアトリビュート名に最も近いアトリビュート名を返す関数
+    // (指定位置で使用可能なものを優先する)
+    // =====================================================================
+    fn suggest_attribute(name: &str, level: CFFIAttributeLevel) -> Option<String> {
+        let candidates = ATTRIBUTE_LEVELS
+            .iter()
+            .filter(|(known, _)| !UNSUPPORTED_ATTRIBUTES.contains(known));
+        let (usable, others): (Vec<_>, Vec<_>) =
+            candidates.partition(|(_, levels)| levels.contains(&level));
+        Self::suggest(name, usable.iter().chain(&others).map(|(known, _)| *known))
+    }
+
+    // =====================================================================
+    // 候補のうち、指定の名前に最も近い名前を返す関数(編集距離が名前の長さの1/3(最低2)以下のもの)
+    // (距離が同じ場合は先の候補を優先する)
+    // =====================================================================
+    pub fn suggest<'a>(
+        name: &str,
+        candidates: impl IntoIterator<Item = &'a str>,
+    ) -> Option<String> {
+        let threshold = (name.chars().count() / 3).max(2);
+        candidates
+            .into_iter()
+            .map(|candidate| (Self::edit_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= threshold)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate.to_string())
+    }
+
+    // =====================================================================
+    // 2つの文字列の編集距離(レーベンシュタイン距離)を返す関数
+    // =====================================================================
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut prev: Vec<usize> = (0..=b.len()).collect();
+        for (i, ca) in a.chars().enumerate() {
+            let mut current = vec![i + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let substitution = prev[j] + usize::from(ca != *cb);
+                current.push(substitution.min(prev[j + 1] + 1).min(current[j] + 1));
+            }
+            prev = current;
+        }
+        prev[b.len()]
+    }
+}
+
+impl CFFIAttribute {
+    // =====================================================================
+    // 値が必要なアトリビュートに値がない場合のエラーを返す関数
+    // =====================================================================
+    fn missing_value(&self, name: &'static str) -> syn::Error {
+        CFFIErrorKind::MissingAttributeValue(name).spanned(&self.tokens)
+    }
+
+    // =====================================================================
+    // アトリビュートの値の形式が不正な場合のエラーを返す関数
+    // =====================================================================
+    fn invalid_value(&self, name: &'static str, expected: &'static str) -> syn::Error {
+        CFFIErrorKind::InvalidAttributeValue(name, expected).spanned(&self.tokens)
+    }
+}
@@ -0,0 +1,825 @@
+use crate::cffi_analyzer::CFFIAnalyzer;
+use crate::cffi_model::*;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Attribute, Generics};
+
+// =====================================================================
+// 中間表現(CFFIModel)からコードを生成する構造体
+// =====================================================================
+pub struct CFFICodegen;
+
+// =====================================================================
+// リンク時解決、実行時解決で共通するラップ関数の構成要素
+// =====================================================================
+struct WrapperParts {
+    // ラップ関数の引数(`x: i32`等)
+    args: Vec<TokenStream>,
+    // 引数の保持、変換処理
+    prelude: TokenStream,
+    // ffi関数に渡す引数名
+    call_idents: Vec<TokenStream>,
+    // ラップ関数の引数名(userdataの引数を除く)
+    arg_idents: Vec<TokenStream>,
+    // ffi関数呼び出し直後の処理(コールバック内のパニックの再開)
+    post_call: TokenStream,
+    // ffi関数呼び出し後の戻り値(result)の処理
+    body: TokenStream,
+    // ラップ関数の戻り値の型
+    return_type: TokenStream,
+}
+
+impl CFFICodegen {
+    // =====================================================================
+    // モデル全体からextern宣言とラップ関数を生成する関数
+    // =====================================================================
+    pub fn generate(model: &CFFIModel) -> TokenStream {
+        let mut output = Self::generate_def_file(model);
+        if let Some(runtime) = &model.library.runtime {
+            output.extend(Self::generate_runtime(model, runtime));
+            return output;
+        }
+
+        for function in &model.functions {
+            if function.optional {
+                output.extend(Self::generate_optional(&model.library, function));
+                continue;
+            }
+            output.extend(Self::generate_extern(&model.library, function));
+            output.extend(Self::generate_wrapper(function));
+        }
+        output
+    }
+
+    // =====================================================================
+    // #[def_file = value]の場合に、.defファイルの変更で再コンパイルさせる定数と、
+    // 宣言されていないエクスポートの一覧(#[def_report_undeclared])を生成する関数
+    // =====================================================================
+    fn generate_def_file(model: &CFFIModel) -> TokenStream {
+        let Some(path) = &model.def_file_path else {
+            return quote! {};
+        };
+        let path = path.display().to_string();
+        let undeclared = model.undeclared_exports.as_ref().map(|exports| {
+            quote! {
+                pub const DEF_UNDECLARED_EXPORTS: &[&str] = &[#(#exports),*];
+            }
+        });
+        quote! {
+            const _: &[u8] = include_bytes!(#path);
+            #undeclared
+        }
+    }
+
+    // =====================================================================
+    // extern宣言の#[link_name]に指定するシンボル名を返す関数
+    // (装飾済みの名前(`_Foo@8`,`?Foo@@YAHXZ`等)は、\x01を付けてLLVMによる再装飾を抑止する)
+    // =====================================================================
+    fn extern_link_name(link_name: &str) -> String {
+        if link_name.contains('@') || link_name.starts_with('?') {
+            format!("\u{1}{link_name}")
+        } else {
+            link_name.to_string()
+        }
+    }
+
+    // =====================================================================
+    // extern宣言を生成する関数
+    // =====================================================================
+    fn generate_extern(library: &LibrarySpec, function: &FunctionSpec) -> TokenStream {
+        let abi = &function.abi;
+        let extern_name = Self::internal_ident(function);
+        let link_name = Self::extern_link_name(&function.link_name);
+        let output_ty = &function.ret.output;
+        let extern_args = function.args.iter().map(|arg| {
+            let ArgSpec {
+                ident, extern_ty, ..
+            } = arg;
+            quote! { #ident: #extern_ty }
+        });
+
+        // ライブラリ名の指定がない場合はリンク指定を行わない(既にリンクされているシンボル等)
+        let link = match (&library.name, &library.link_type) {
+            (Some(lib_name), Some(link_type)) => {
+                quote! { link(name = #lib_name, kind = #link_type) }
+            }
+            (Some(lib_name), None) => quote! { link(name = #lib_name) },
+            (None, _) => quote! {},
+        };
+        // #[link_cfg = value]の場合、条件を満たす場合のみリンク指定を行う
+        let link_attr = match (&library.name, &library.link_cfg) {
+            (None, _) => quote! {},
+            (Some(_), Some(link_cfg)) => quote! { #[cfg_attr(#link_cfg, #link)] },
+            (Some(_), None) => quote! { #[#link] },
+        };
+        let cfg_attrs = Self::cfg_attrs(function);
+        quote! {
+            #(#cfg_attrs)*
+            #link_attr
+            unsafe extern #abi {
+                #[link_name = #link_name]
+                fn #extern_name(#(#extern_args),*) #output_ty;
+            }
+        }
+    }
+
+    // =====================================================================
+    // ラップ関数を生成する関数
+    // =====================================================================
+    fn generate_wrapper(function: &FunctionSpec) -> TokenStream {
+        let FunctionSpec {
+            wrapper_name,
+            vis,
+            generics,
+            ret,
+            attrs,
+            ..
+        } = function;
+        let extern_name = Self::internal_ident(function);
+        let return_type = &ret.ty;
+        let WrapperParts {
+            args,
+            prelude,
+            call_idents,
+            post_call,
+            body,
+            return_type: wrapper_return_type,
+            ..
+        } = Self::wrapper_parts(function, false);
+
+        quote! {
+            #(#attrs)*
+            #vis fn #wrapper_name #generics( #(#args),* ) -> #wrapper_return_type {
+                #prelude
+
+                unsafe {
+                    let result: #return_type = #extern_name(#(#call_idents),*);
+                    #post_call
+                    #body
+                }
+            }
+        }
+    }
+
+    // =====================================================================
+    // ラップ関数の構成要素を生成する関数
+    // (runtimeの場合、ライブラリ、シンボルが存在しない場合もエラーを返すため常にResultを返す)
+    // =====================================================================
+    fn wrapper_parts(function: &FunctionSpec, runtime: bool) -> WrapperParts {
+        let FunctionSpec {
+            args,
+            ret,
+            error_policy,
+            ..
+        } = function;
+        let return_type = &ret.ty;
+
+        // userdataの引数はコールバックの変換処理で値を決めるため、ラップ関数の引数にしない
+        let visible_args = args
+            .iter()
+            .filter(|arg| !matches!(arg.conversion, ArgConversion::CallbackUserdata));
+        let wrapper_args = visible_args
+            .clone()
+            .map(|arg| {
+                let ArgSpec {
+                    ident, wrapper_ty, ..
+                } = arg;
+                quote! { #ident: #wrapper_ty }
+            })
+            .collect();
+        let arg_idents = visible_args
+            .map(|arg| {
+                let ident = &arg.ident;
+                quote! { #ident }
+            })
+            .collect();
+
+        // 呼び出し後も保持されるコールバックがある場合、戻り値と共にCallbackRegistrationを返す
+        let callbacks = args.iter().filter_map(|arg| match &arg.conversion {
+            ArgConversion::Callback(callback) => Some((&arg.ident, callback)),
+            _ => None,
+        });
+        let registered = callbacks.clone().any(|(_, callback)| callback.registered);
+        let registration = registered.then(|| {
+            quote! { let mut __cffi_registration = ::cffi_gen::callback::CallbackRegistration::new(); }
+        });
+        // 呼び出し中のみ使用するコールバックのパニックは、呼び出し元で再開する
+        let resume_panics =
+            callbacks
+                .filter(|(_, callback)| !callback.registered)
+                .map(|(ident, _)| {
+                    let state_ident = format_ident!("__{}_state", ident);
+                    quote! { #state_ident.resume_panic(); }
+                });
+        let (ok_value, return_type) = if registered {
+            (
+                quote! { (result, __cffi_registration) },
+                quote! { (#return_type, ::cffi_gen::callback::CallbackRegistration) },
+            )
+        } else {
+            (quote! { result }, quote! { #return_type })
+        };
+        // ラップ関数がResultを返す場合、引数の変換エラーもErrとして返す
+        let returns_result = error_policy.as_result || runtime;
+        let convert_stmts = args
+            .iter()
+            .map(|arg| Self::generate_convert_stmt(function, arg, returns_result));
+        let call_idents = args
+            .iter()
+            .map(|arg| {
+                let ident = &arg.ident;
+                quote! { #ident }
+            })
+            .collect();
+
+        let ErrorPolicy {
+            as_result,
+            condition,
+            error_type,
+            error_value,
+            args_capture,
+        } = error_policy;
+
+        // エラー条件がない場合はエラー判定を行わない
+        // (as_resultでない場合は戻り値をそのまま返す)
+        let body = match (as_result, condition) {
+            (true, Some(condition)) => quote! {
+                if #condition {
+                    return Err(#error_value);
+                } else {
+                    return Ok(#ok_value);
+                }
+            },
+            (true, None) => quote! { Ok(#ok_value) },
+            (false, _) if runtime => quote! { Ok(#ok_value) },
+            (false, _) => quote! { #ok_value },
+        };
+        let wrapper_return_type = match (as_result, runtime) {
+            (true, _) => quote! { ::std::result::Result<#return_type, #error_type> },
+            (false, true) => quote! { ::std::result::Result<#return_type, ::cffi_gen::FfiError> },
+            (false, false) => quote! { #return_type },
+        };
+
+        WrapperParts {
+            args: wrapper_args,
+            prelude: quote! {
+                #args_capture
+                #registration
+                #(#convert_stmts)*
+            },
+            call_idents,
+            arg_idents,
+            post_call: quote! { #(#resume_panics)* },
+            body,
+            return_type: wrapper_return_type,
+        }
+    }
+
+    // =====================================================================
+    // #[link_type = "runtime"]の場合に、ライブラリ構造体とラップ関数を生成する関数
+    // =====================================================================
+    fn generate_runtime(model: &CFFIModel, runtime: &RuntimeSpec) -> TokenStream {
+        let RuntimeSpec {
+            struct_name,
+            global_name,
+            eager,
+        } = runtime;
+        let lib_name = model.library.name.as_deref().unwrap_or_default();
+        let functions = &model.functions;
+
+        let fields = functions.iter().map(|function| {
+            let field = Self::internal_ident(function);
+            let fn_type = Self::fn_pointer_type(function);
+            let cfg_attrs = Self::cfg_attrs(function);
+            quote! { #(#cfg_attrs)* #field: ::cffi_gen::runtime::LazySymbol<#fn_type> }
+        });
+        let field_inits = functions.iter().map(|function| {
+            let field = Self::internal_ident(function);
+            let cfg_attrs = Self::cfg_attrs(function);
+            quote! { #(#cfg_attrs)* #field: ::cffi_gen::runtime::LazySymbol::new() }
+        });
+        // #[optional]の関数は存在しなくてもエラーにしない
+        let resolve_stmts = functions.iter().filter(|function| !function.optional).map(
+            |function| {
+                let field = Self::internal_ident(function);
+                let link_name = &function.link_name;
+                let cfg_attrs = Self::cfg_attrs(function);
+                quote! { #(#cfg_attrs)* unsafe { self.#field.get(&self.library, #link_name) }?; }
+            },
+        );
+        let eager_stmt = eager.then(|| quote! { library.resolve_all()?; });
+        let methods = functions.iter().map(Self::generate_runtime_method);
+        let global_wrappers = functions
+            .iter()
+            .map(|function| Self::generate_runtime_global_wrapper(function, struct_name));
+
+        quote! {
+            pub struct #struct_name {
+                library: ::cffi_gen::runtime::Library,
+                #(#fields,)*
+            }
+
+            static #global_name: ::std::sync::OnceLock<#struct_name> = ::std::sync::OnceLock::new();
+
+            impl #struct_name {
+                pub const LIBRARY_NAME: &'static str = #lib_name;
+
+                // プラットフォームごとのファイル名(DxLib_x64.dll、libc.so等)で読み込む
+                pub fn load() -> ::std::result::Result<Self, ::cffi_gen::FfiError> {
+                    Self::load_from(::cffi_gen::runtime::library_filename(Self::LIBRARY_NAME))
+                }
+
+                // 指定パスのライブラリを読み込む
+                pub fn load_from(
+                    path: impl ::std::convert::AsRef<::std::ffi::OsStr>,
+                ) -> ::std::result::Result<Self, ::cffi_gen::FfiError> {
+                    let library = Self {
+                        library: ::cffi_gen::runtime::Library::open(path)?,
+                        #(#field_inits,)*
+                    };
+                    #eager_stmt
+                    Ok(library)
+                }
+
+                // 全てのシンボルを解決する(存在しないシンボルがあればエラー)
+                pub fn resolve_all(&self) -> ::std::result::Result<(), ::cffi_gen::FfiError> {
+                    #(#resolve_stmts)*
+                    Ok(())
+                }
+
+                pub fn library(&self) -> &::cffi_gen::runtime::Library {
+                    &self.library
+                }
+
+                // グローバルインスタンスを返す(未設定の場合はload()で読み込む)
+                pub fn global() -> ::std::result::Result<&'static Self, ::cffi_gen::FfiError> {
+                    if let Some(library) = #global_name.get() {
+                        return Ok(library);
+                    }
+                    let library = Self::load()?;
+                    Ok(#global_name.get_or_init(|| library))
+                }
+
+                // グローバルインスタンスを設定する(既に設定されている場合はErrで返す)
+                pub fn set_global(library: Self) -> ::std::result::Result<(), Self> {
+                    #global_name.set(library)
+                }
+
+                #(#methods)*
+            }
+
+            #(#global_wrappers)*
+        }
+    }
+
+    // =====================================================================
+    // ライブラリ構造体のメソッドとしてラップ関数を生成する関数
+    // =====================================================================
+    fn generate_runtime_method(function: &FunctionSpec) -> TokenStream {
+        let field = Self::internal_ident(function);
+        let link_name = &function.link_name;
+        let symbol = quote! { unsafe { self.#field.get(&self.library, #link_name) } };
+
+        let mut output = Self::generate_symbol_wrapper(function, quote! { &self, }, &symbol);
+        if function.optional {
+            let vis = &function.vis;
+            let available_name = Self::available_name(function);
+            let cfg_attrs = Self::cfg_attrs(function);
+            output.extend(quote! {
+                #(#cfg_attrs)*
+                #vis fn #available_name(&self) -> bool {
+                    #symbol.is_ok()
+                }
+            });
+        }
+        output
+    }
+
+    // =====================================================================
+    // グローバルインスタンスを使用するラップ関数を生成する関数
+    // =====================================================================
+    fn generate_runtime_global_wrapper(
+        function: &FunctionSpec,
+        struct_name: &Ident,
+    ) -> TokenStream {
+        let FunctionSpec {
+            wrapper_name,
+            vis,
+            generics,
+            attrs,
+            ..
+        } = function;
+        let WrapperParts {
+            args,
+            arg_idents,
+            return_type: wrapper_return_type,
+            ..
+        } = Self::wrapper_parts(function, true);
+
+        // #[deprecated]の指定がある場合、メソッドの呼び出しで警告しないようにする
+        // (呼び出し元への警告は、ラップ関数自体に付与した#[deprecated]で行われる)
+        let mut output = quote! {
+            #(#attrs)*
+            #[allow(deprecated)]
+            #vis fn #wrapper_name #generics( #(#args),* ) -> #wrapper_return_type {
+                match #struct_name::global() {
+                    Ok(library) => library.#wrapper_name(#(#arg_idents),*),
+                    Err(e) => Err(::std::convert::From::from(e)),
+                }
+            }
+        };
+        if function.optional {
+            let available_name = Self::available_name(function);
+            let cfg_attrs = Self::cfg_attrs(function);
+            output.extend(quote! {
+                #(#cfg_attrs)*
+                #vis fn #available_name() -> bool {
+                    #struct_name::global().is_ok_and(|library| library.#available_name())
+                }
+            });
+        }
+        output
+    }
+
+    // =====================================================================
+    // #[optional]の関数について、初回呼び出し時にシンボルを解決するラップ関数を生成する関数
+    // (シンボルはLibrary::sharedで共有するライブラリから解決する)
+    // =====================================================================
+    fn generate_optional(library: &LibrarySpec, function: &FunctionSpec) -> TokenStream {
+        // 大文字小文字のみ異なる関数(init,Init等)で衝突しないよう、ラップ関数名をそのまま使用する
+        let static_name = format_ident!("__CFFI_SYMBOL_{}", function.wrapper_name.unraw());
+        let fn_type = Self::fn_pointer_type(function);
+        let link_name = &function.link_name;
+        let lib_name = match &library.name {
+            Some(lib_name) => quote! { ::std::option::Option::Some(#lib_name) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let symbol = quote! {
+            ::cffi_gen::runtime::Library::shared(#lib_name)
+                .and_then(|library| unsafe { #static_name.get(library, #link_name) })
+        };
+        let wrapper = Self::generate_symbol_wrapper(function, quote! {}, &symbol);
+        let vis = &function.vis;
+        let available_name = Self::available_name(function);
+        let cfg_attrs = Self::cfg_attrs(function);
+
+        quote! {
+            #(#cfg_attrs)*
+            #[allow(non_upper_case_globals)]
+            static #static_name: ::cffi_gen::runtime::LazySymbol<#fn_type> =
+                ::cffi_gen::runtime::LazySymbol::new();
+
+            #wrapper
+
+            #(#cfg_attrs)*
+            #vis fn #available_name() -> bool {
+                #symbol.is_ok()
+            }
+        }
+    }
+
+    // =====================================================================
+    // 実行時に解決したシンボル(関数ポインタ)を呼び出すラップ関数を生成する関数
+    // (symbolはResult<関数ポインタ, FfiError>となる式)
+    // =====================================================================
+    fn generate_symbol_wrapper(
+        function: &FunctionSpec,
+        receiver: TokenStream,
+        symbol: &TokenStream,
+    ) -> TokenStream {
+        let FunctionSpec {
+            wrapper_name,
+            vis,
+            generics,
+            ret,
+            attrs,
+            ..
+        } = function;
+        let return_type = &ret.ty;
+        let WrapperParts {
+            args,
+            prelude,
+            call_idents,
+            post_call,
+            body,
+            return_type: wrapper_return_type,
+            ..
+        } = Self::wrapper_parts(function, true);
+
+        quote! {
+            #(#attrs)*
+            #vis fn #wrapper_name #generics(#receiver #(#args),* ) -> #wrapper_return_type {
+                let __cffi_fn = match #symbol {
+                    Ok(symbol) => symbol,
+                    Err(e) => return Err(::std::convert::From::from(e)),
+                };
+                #prelude
+
+                unsafe {
+                    let result: #return_type = __cffi_fn(#(#call_idents),*);
+                    #post_call
+                    #body
+                }
+            }
+        }
+    }
+
+    // =====================================================================
+    // extern宣言に相当する関数ポインタの型を生成する関数
+    // =====================================================================
+    fn fn_pointer_type(function: &FunctionSpec) -> TokenStream {
+        let abi = &function.abi;
+        let output_ty = &function.ret.output;
+        let extern_tys = function.args.iter().map(|arg| &arg.extern_ty);
+        quote! { unsafe extern #abi fn(#(#extern_tys),*) #output_ty }
+    }
+
+    // =====================================================================
+    // ラップ関数以外の生成物(extern宣言、シンボルの保持先等)にも付与する#[cfg]を返す関数
+    // =====================================================================
+    fn cfg_attrs(function: &FunctionSpec) -> Vec<&Attribute> {
+        function
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("cfg"))
+            .collect()
+    }
+
+    // =====================================================================
+    // #[optional]の関数のシンボルが存在するかを返す関数名({ラップ関数名}_is_available)
+    // =====================================================================
+    fn available_name(function: &FunctionSpec) -> Ident {
+        format_ident!("{}_is_available", function.wrapper_name)
+    }
+
+    // =====================================================================
+    // extern宣言の関数名、ライブラリ構造体でシンボルを保持するフィールド名を返す関数
+    // (シンボル名はlink_nameで指定するため、ラップ関数名から生成する)
+    // =====================================================================
+    fn internal_ident(function: &FunctionSpec) -> Ident {
+        format_ident!("__cffi_{}", function.wrapper_name)
+    }
+
+    // =====================================================================
+    // #[callback]のクロージャをトランポリンの関数ポインタとuserdataに変換する処理を生成する関数
+    // (クロージャはCallbackStateで保持し、userdataとしてその参照を渡す)
+    // =====================================================================
+    fn generate_callback_stmt(
+        function: &FunctionSpec,
+        ident: &Ident,
+        callback: &CallbackSpec,
+        returns_result: bool,
+    ) -> TokenStream {
+        let CallbackSpec {
+            bound,
+            inputs,
+            output,
+            abi,
+            context,
+            registered,
+        } = callback;
+        let (userdata, position) = match context {
+            CallbackContext::Userdata { arg, position } => (arg, *position),
+            CallbackContext::StaticSlots(slots) => {
+                return Self::generate_static_slots_stmt(
+                    function,
+                    ident,
+                    callback,
+                    *slots,
+                    returns_result,
+                );
+            }
+        };
+        let state_ident = format_ident!("__{}_state", ident);
+        let trampoline_ident = format_ident!("__{}_trampoline", ident);
+        let input_idents = (0..inputs.len())
+            .map(|i| format_ident!("arg{}", i))
+            .collect::<Vec<_>>();
+        let fn_type = callback.fn_type();
+        // userdataは指定された位置の引数で受け取る
+        let mut params = input_idents
+            .iter()
+            .zip(inputs)
+            .map(|(input_ident, ty)| quote! { #input_ident: #ty })
+            .collect::<Vec<_>>();
+        params.insert(position, quote! { userdata: *mut ::std::ffi::c_void });
+
+        // クロージャの型ごとにトランポリンを単相化する
+        let trampoline = quote! {
+            fn #trampoline_ident<F: #bound>(
+                _: &::cffi_gen::callback::CallbackState<F>,
+            ) -> #fn_type {
+                unsafe extern #abi fn trampoline<F: #bound>(#(#params),*) #output {
+                    unsafe {
+                        ::cffi_gen::callback::CallbackState::<F>::call(userdata, |f| {
+                            f(#(#input_idents),*)
+                        })
+                    }
+                }
+                trampoline::<F>
+            }
+        };
+        if *registered {
+            // 呼び出し後もC側で保持されるため、Boxで確保してCallbackRegistrationに所有させる
+            quote! {
+                #trampoline
+                let #state_ident = ::std::boxed::Box::new(
+                    ::cffi_gen::callback::CallbackState::new(#ident),
+                );
+                let #userdata = #state_ident.as_userdata() as _;
+                let #ident = #trampoline_ident(&*#state_ident);
+                __cffi_registration.push(#state_ident);
+            }
+        } else {
+            quote! {
+                #trampoline
+                let #state_ident = ::cffi_gen::callback::CallbackState::new(#ident);
+                let #userdata = #state_ident.as_userdata() as _;
+                let #ident = #trampoline_ident(&#state_ident);
+            }
+        }
+    }
+
+    // =====================================================================
+    // #[callback(static_slots = N)]のクロージャをスロットに登録し、
+    // スロットに対応するトランポリンの関数ポインタに変換する処理を生成する関数
+    // (テーブルとトランポリンはラップ関数内のstatic、関数として生成する)
+    // =====================================================================
+    fn generate_static_slots_stmt(
+        function: &FunctionSpec,
+        ident: &Ident,
+        callback: &CallbackSpec,
+        slots: usize,
+        returns_result: bool,
+    ) -> TokenStream {
+        let CallbackSpec {
+            bound,
+            inputs,
+            output,
+            abi,
+            registered,
+            ..
+        } = callback;
+        let state_ident = format_ident!("__{}_state", ident);
+        let table_ident = format_ident!("__CFFI_{}_SLOTS", ident.to_string().to_uppercase());
+        let slot_idents = (0..slots)
+            .map(|i| format_ident!("__{}_slot{}", ident, i))
+            .collect::<Vec<_>>();
+        let input_idents = (0..inputs.len())
+            .map(|i| format_ident!("arg{}", i))
+            .collect::<Vec<_>>();
+        let fn_type = callback.fn_type();
+        // スロットごとに、自身の番号のクロージャを呼び出すトランポリンを生成する
+        let trampolines = slot_idents.iter().enumerate().map(|(index, slot_ident)| {
+            quote! {
+                unsafe extern #abi fn #slot_ident(#(#input_idents: #inputs),*) #output {
+                    #table_ident.call(#index, |f| f(#(#input_idents),*))
+                }
+            }
+        });
+
+        let function_name = function.wrapper_name.to_string();
+        let arg_name = ident.to_string();
+        let on_error = Self::generate_argument_error(
+            quote! {
+                ::cffi_gen::FfiError::CallbackSlotsExhausted {
+                    function: #function_name,
+                    arg: #arg_name,
+                    slots: #slots,
+                }
+            },
+            returns_result,
+        );
+        // 呼び出し中のみ使用する場合は、ラップ関数の終了時にスロットを解放する
+        let register = registered.then(|| quote! { __cffi_registration.push_slot(#state_ident); });
+
+        quote! {
+            static #table_ident: ::cffi_gen::callback::StaticSlots<
+                dyn #bound + ::std::marker::Send + 'static,
+                #slots,
+            > = ::cffi_gen::callback::StaticSlots::new();
+            #(#trampolines)*
+            let #state_ident = match #table_ident.register(::std::boxed::Box::new(#ident)) {
+                Some(slot) => slot,
+                None => #on_error,
+            };
+            let #ident: #fn_type = [#(#slot_idents as #fn_type),*][#state_ident.index()];
+            #register
+        }
+    }
+
+    // =====================================================================
+    // 引数の変換に失敗した場合の処理を生成する関数
+    // (Resultを返す場合はErr、それ以外はpanicする)
+    // =====================================================================
+    fn generate_argument_error(ffi_error: TokenStream, returns_result: bool) -> TokenStream {
+        if returns_result {
+            quote! { return Err(::std::convert::From::from(#ffi_error)) }
+        } else {
+            quote! { ::std::panic!("{}", #ffi_error) }
+        }
+    }
+
+    // =====================================================================
+    // #[repr(C)]の型をそのまま値渡しするIntoFfiArgの実装と、
+    // 参照をポインタとして渡すためのReprCの実装を生成する関数
+    // (#[derive(IntoFfiArg)]、ヘッダーから生成した構造体で使用)
+    // =====================================================================
+    pub fn generate_into_ffi_arg_passthrough(ident: &Ident, generics: &Generics) -> TokenStream {
+        let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+        quote! {
+            impl #impl_generics ::cffi_gen::IntoFfiArg for #ident #ty_generics #where_clause {
+                type CType = Self;
+                type Guard = ();
+                fn into_ffi(self) -> (Self::CType, Self::Guard) {
+                    (self, ())
+                }
+            }
+
+            unsafe impl #impl_generics ::cffi_gen::ReprC for #ident #ty_generics #where_clause {}
+        }
+    }
+
+    // =====================================================================
+    // 引数の変換処理を生成する関数
+    // =====================================================================
+    fn generate_convert_stmt(
+        function: &FunctionSpec,
+        arg: &ArgSpec,
+        returns_result: bool,
+    ) -> TokenStream {
+        let ident = &arg.ident;
+        // ガードは呼び出しが終わるまでスコープに残す
+        let guard_ident = format_ident!("__{}_guard", ident);
+        match &arg.conversion {
+            ArgConversion::PassThrough => quote! {},
+            ArgConversion::OptionDefault(default_expr) => quote! {
+                let #ident = match #ident {
+                    Some(value) => value,
+                    None => #default_expr,
+                };
+            },
+            ArgConversion::Callback(callback) => {
+                Self::generate_callback_stmt(function, ident, callback, returns_result)
+            }
+            // 値はコールバックの変換処理で設定する
+            ArgConversion::CallbackUserdata => quote! {},
+            ArgConversion::IntoFfi { source, mutable } => {
+                let rebind = mutable.then(|| quote! { let mut #ident = #ident; });
+                if !returns_result {
+                    return quote! {
+                        #rebind
+                        let (#ident, #guard_ident) = ::cffi_gen::IntoFfiArg::into_ffi(#source);
+                    };
+                }
+                // Resultを返す場合は、変換の失敗(NULを含む文字列等)もErrとして返す
+                let function_name = function.wrapper_name.to_string();
+                let arg_name = ident.to_string();
+                let extern_ty = &arg.extern_ty;
+                let on_error = Self::generate_argument_error(
+                    quote! {
+                        ::cffi_gen::FfiError::InvalidArgument {
+                            function: #function_name,
+                            arg: #arg_name,
+                            ty: ::std::any::type_name::<#extern_ty>(),
+                            reason: ::std::string::ToString::to_string(&e),
+                        }
+                    },
+                    returns_result,
+                );
+                quote! {
+                    #rebind
+                    let (#ident, #guard_ident) =
+                        match ::cffi_gen::IntoFfiArg::try_into_ffi(#source) {
+                            Ok(value) => value,
+                            Err(e) => #on_error,
+                        };
+                }
+            }
+            ArgConversion::TryInto(target) => {
+                let function_name = function.wrapper_name.to_string();
+                let arg_name = ident.to_string();
+                let target_name = CFFIAnalyzer::tokens_to_string(target);
+                let ffi_error = quote! {
+                    ::cffi_gen::FfiError::InvalidArgument {
+                        function: #function_name,
+                        arg: #arg_name,
+                        ty: #target_name,
+                        reason: ::std::string::ToString::to_string(&e),
+                    }
+                };
+                let on_error = Self::generate_argument_error(ffi_error, returns_result);
+                quote! {
+                    let #ident: #target = match ::std::convert::TryFrom::try_from(#ident) {
+                        Ok(value) => value,
+                        Err(e) => #on_error,
+                    };
+                }
+            }
+            ArgConversion::Lossy(target) => quote! {
+                let #ident = #ident as #target;
+            },
+        }
+    }
+}
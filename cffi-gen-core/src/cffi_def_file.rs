@@ -0,0 +1,116 @@
+use crate::cffi_analyzer::*;
+use crate::cffi_attribute_analyzer::*;
+use crate::cffi_error::*;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use syn::LitStr;
+
+// =====================================================================
+// #[def_file = value]で指定されたモジュール定義ファイル(.def)
+// =====================================================================
+pub struct CFFIDefFile {
+    // CARGO_MANIFEST_DIRからの絶対パス(再コンパイル判定用のinclude_bytes!に使用)
+    pub path: PathBuf,
+    // 指定された値(エラーメッセージ用)
+    pub name: String,
+    // EXPORTSセクションに記載されたシンボル名
+    pub exports: Vec<String>,
+}
+
+impl CFFIDefFile {
+    // =====================================================================
+    // .defファイルを読み込む関数(相対パスはCARGO_MANIFEST_DIRからのパスとして扱う)
+    // =====================================================================
+    pub fn load(lit: &LitStr) -> syn::Result<Self> {
+        let name = lit.value();
+        let path = CFFIAnalyzer::manifest_path(&name);
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            CFFIErrorKind::UnreadableFile(path.display().to_string(), e.to_string()).spanned(lit)
+        })?;
+        Ok(CFFIDefFile {
+            path,
+            name,
+            exports: Self::parse_exports(&content),
+        })
+    }
+
+    // =====================================================================
+    // EXPORTSセクションのシンボル名を取り出す関数
+    // (`name`,`name=internal`,`name @1 NONAME`,`name DATA`等の形式に対応、`;`以降はコメント)
+    // =====================================================================
+    fn parse_exports(content: &str) -> Vec<String> {
+        // EXPORTS以外のセクション(LIBRARY,HEAPSIZE等)
+        const SECTIONS: &[&str] = &[
+            "NAME",
+            "LIBRARY",
+            "DESCRIPTION",
+            "STACKSIZE",
+            "SECTIONS",
+            "VERSION",
+            "HEAPSIZE",
+            "IMPORTS",
+        ];
+
+        let mut exports = Vec::new();
+        let mut in_exports = false;
+        for line in content.lines() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            let Some(first) = line.split_whitespace().next() else {
+                continue;
+            };
+            if first == "EXPORTS" {
+                in_exports = true;
+                // `EXPORTS name`のように同じ行に記載される場合もある
+                if let Some(name) = line.split_whitespace().nth(1) {
+                    exports.push(Self::entry_name(name));
+                }
+                continue;
+            }
+            if SECTIONS.contains(&first) {
+                in_exports = false;
+                continue;
+            }
+            if in_exports {
+                exports.push(Self::entry_name(first));
+            }
+        }
+        exports
+    }
+
+    // =====================================================================
+    // エクスポート定義からシンボル名を取り出す関数(`name=internal` -> name)
+    // =====================================================================
+    fn entry_name(entry: &str) -> String {
+        let name = entry.split('=').next().unwrap_or(entry);
+        name.trim_matches('"').to_string()
+    }
+
+    // =====================================================================
+    // 指定シンボルがEXPORTSに存在しない場合、最も近いシンボル名と共にエラーを返す関数
+    // =====================================================================
+    pub fn validate(&self, symbol: &str, span: proc_macro2::Span) -> syn::Result<()> {
+        if self.exports.iter().any(|export| export == symbol) {
+            return Ok(());
+        }
+        let suggestion =
+            CFFIAttributeAnalyzer::suggest(symbol, self.exports.iter().map(String::as_str));
+        Err(CFFIErrorKind::UndefinedExport {
+            symbol: symbol.to_string(),
+            def_file: self.name.clone(),
+            suggestion,
+        }
+        .at(span))
+    }
+
+    // =====================================================================
+    // 宣言されていないエクスポートを返す関数(#[def_report_undeclared]指定時)
+    // =====================================================================
+    pub fn undeclared<'a>(&self, declared: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let declared = declared.into_iter().collect::<HashSet<_>>();
+        self.exports
+            .iter()
+            .filter(|export| !declared.contains(export.as_str()))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,194 @@
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+use thiserror::Error;
+
+// =====================================================================
+// マクロ展開時のエラーの種類(メッセージはここで一元管理する)
+// =====================================================================
+#[derive(Debug, Error)]
+pub enum CFFIErrorKind {
+    #[error("unsupported pattern argument; arguments must be plain identifiers like `x: i32`")]
+    UnsupportedPatternArgument,
+    #[error("`self` arguments are not supported in foreign function declarations")]
+    UnsupportedReceiver,
+    #[error("unknown ABI `{0}`; expected one of {1}")]
+    UnknownAbi(String, String),
+    #[error("`{0}` is not a valid Rust identifier")]
+    InvalidIdentifier(String),
+    #[error("invalid expression in `{0}`: {1}")]
+    InvalidExpression(&'static str, String),
+    #[error("invalid type in `{0}`: {1}")]
+    InvalidType(&'static str, String),
+    #[error("`{0}` requires a value (`#[{0} = ...]`)")]
+    MissingAttributeValue(&'static str),
+    #[error("invalid `error_condition` preset; expected `{0}`")]
+    InvalidErrorConditionPreset(&'static str),
+    #[error("unknown attribute `{name}`{}", did_you_mean(.suggestion))]
+    UnknownAttribute {
+        name: String,
+        suggestion: Option<String>,
+    },
+    #[error("`{0}` cannot be used at {1} level; allowed at: {2}")]
+    MisplacedAttribute(String, &'static str, String),
+    #[error("invalid value for `{0}`; expected {1}")]
+    InvalidAttributeValue(&'static str, &'static str),
+    #[error("`link_type = \"runtime\"` requires `library_name`")]
+    RuntimeWithoutLibraryName,
+    #[error("failed to read `{0}`: {1}")]
+    UnreadableFile(String, String),
+    #[error("invalid spec file `{file}`: {reason}")]
+    InvalidSpecFile { file: String, reason: String },
+    #[error("invalid pattern `{0}`: {1}")]
+    InvalidPattern(String, String),
+    #[error("`{name}` is not declared in the header{}", did_you_mean(.suggestion))]
+    UndeclaredHeaderFunction {
+        name: String,
+        suggestion: Option<String>,
+    },
+    #[error("`{symbol}` is not exported by `{def_file}`{}", did_you_mean(.suggestion))]
+    UndefinedExport {
+        symbol: String,
+        def_file: String,
+        suggestion: Option<String>,
+    },
+    #[error("expected `#[cffi]` or `#[cffi(...)]`")]
+    InvalidCFFIAttribute,
+    #[error("#[cffi_module] requires an inline module body")]
+    ModuleWithoutBody,
+    #[error("#[cffi] functions must be declarations without a body (`fn name(...) -> T;`)")]
+    FunctionWithBody,
+    #[error("`{0}` must be `#[repr(C)]` or `#[repr(transparent)]` to be passed by value")]
+    NotReprC(String),
+    #[error("invalid `#[callback]`: {0}")]
+    InvalidCallback(String),
+    #[error("unsupported argument type `{0}`; {1}")]
+    UnsupportedImplTrait(String, &'static str),
+    #[error(
+        "`{0}` conflicts with a method of the generated library struct; rename the wrapper with `#[alias = ...]`"
+    )]
+    ReservedRuntimeName(String),
+    #[error(
+        "`{name}` conflicts with the availability check generated for #[optional] `{function}`; rename the wrapper with `#[alias = ...]`"
+    )]
+    AvailableNameConflict { name: String, function: String },
+    #[error("`{0}` is not supported yet")]
+    UnsupportedAttribute(String),
+}
+
+impl CFFIErrorKind {
+    // =====================================================================
+    // 指定トークンの位置を指すsyn::Errorに変換する関数
+    // =====================================================================
+    pub fn spanned(self, tokens: impl ToTokens) -> syn::Error {
+        syn::Error::new_spanned(tokens, self.to_string())
+    }
+
+    // =====================================================================
+    // 指定Spanを指すsyn::Errorに変換する関数
+    // =====================================================================
+    pub fn at(self, span: Span) -> syn::Error {
+        syn::Error::new(span, self.to_string())
+    }
+}
+
+// =====================================================================
+// 候補がある場合に提案文を返す関数
+// =====================================================================
+fn did_you_mean(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|suggestion| format!("; did you mean `{suggestion}`?"))
+        .unwrap_or_default()
+}
+
+// =====================================================================
+// マクロ呼び出し1回分のエラーを収集する構造体
+// (最初のエラーで止めず、全ての問題をまとめてcompile_error!として出力する)
+// =====================================================================
+#[derive(Debug, Default)]
+pub struct CFFIError {
+    errors: Vec<syn::Error>,
+}
+
+impl CFFIError {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // =====================================================================
+    // エラーを追加する関数
+    // =====================================================================
+    pub fn push(&mut self, error: syn::Error) {
+        self.errors.push(error);
+    }
+
+    // =====================================================================
+    // 別のCFFIErrorのエラーを全て取り込む関数
+    // =====================================================================
+    pub fn extend(&mut self, other: CFFIError) {
+        self.errors.extend(other.errors);
+    }
+
+    // =====================================================================
+    // Resultがエラーの場合は収集してNoneを返し、成功の場合は値を返す関数
+    // =====================================================================
+    pub fn collect<T, E: Into<CFFIError>>(&mut self, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.extend(e.into());
+                None
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    // =====================================================================
+    // エラーが無ければ指定値をOkで、あればErrで返す関数
+    // =====================================================================
+    pub fn finish<T>(self, value: T) -> Result<T, CFFIError> {
+        if self.is_empty() {
+            Ok(value)
+        } else {
+            Err(self)
+        }
+    }
+
+    // =====================================================================
+    // 収集したエラーをcompile_error!群として出力する関数
+    // =====================================================================
+    pub fn to_compile_error(&self) -> TokenStream {
+        self.errors
+            .iter()
+            .map(syn::Error::to_compile_error)
+            .collect()
+    }
+}
+
+// =====================================================================
+// 収集したエラーのメッセージを1行ずつ出力する(ビルドスクリプト用APIで使用)
+// =====================================================================
+impl std::fmt::Display for CFFIError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages = self
+            .errors
+            .iter()
+            .map(syn::Error::to_string)
+            .collect::<Vec<_>>();
+        f.write_str(&messages.join("\n"))
+    }
+}
+
+impl std::error::Error for CFFIError {}
+
+impl From<syn::Error> for CFFIError {
+    fn from(error: syn::Error) -> Self {
+        // syn::Errorは複数のエラーを結合している場合がある
+        CFFIError {
+            errors: error.into_iter().collect(),
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::cffi_analyzer::*;
+use crate::cffi_attribute_analyzer::*;
+use crate::cffi_codegen::*;
+use crate::cffi_error::*;
+use crate::cffi_header::*;
+use crate::cffi_header_codegen::*;
+use crate::cffi_model::*;
+use crate::cffi_spec_file::*;
+use crate::structs::*;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, LitStr};
+
+// =====================================================================
+// config属性と関数宣言群から、extern宣言とラップ関数を生成する関数
+// (cffi_gen!,#[cffi_module],#[cffi],ビルドスクリプト用APIで共通して使用)
+// =====================================================================
+pub fn expand_cffi_gen<'a>(
+    config_attrs: &[Attribute],
+    fns: impl IntoIterator<Item = &'a FunctionWithAttrs>,
+) -> Result<TokenStream, CFFIError> {
+    let model = CFFIModel::build(config_attrs, fns)?;
+    Ok(CFFICodegen::generate(&model))
+}
+
+// =====================================================================
+// 宣言ファイル(spec = value)から、cffi_gen!と同じextern宣言とラップ関数を生成する関数
+// =====================================================================
+pub fn expand_cffi_gen_from_spec(spec: &LitStr) -> Result<TokenStream, CFFIError> {
+    let spec_file = CFFISpecFile::load(spec)?;
+    let bindings = expand_cffi_gen(&spec_file.config_attrs, &spec_file.functions)?;
+    let path = spec_file.path.display().to_string();
+    Ok(quote! {
+        // 宣言ファイルの変更時に再コンパイルさせる
+        const _: &[u8] = include_bytes!(#path);
+        #bindings
+    })
+}
+
+// =====================================================================
+// ヘッダーを解析し、型、定数の定義とcffi_gen!と同じextern宣言、ラップ関数を生成する関数
+// =====================================================================
+pub fn expand_cffi_gen_from_header(input: CFFIHeaderInput) -> Result<TokenStream, CFFIError> {
+    let CFFIHeaderInput {
+        header,
+        config_attrs,
+        calling_conventions,
+        ignore,
+        allow,
+        block,
+        overrides,
+    } = input;
+    let mut errors = CFFIError::new();
+
+    let path = CFFIAnalyzer::manifest_path(&header.value());
+    let source = std::fs::read_to_string(&path).map_err(|e| {
+        CFFIError::from(
+            CFFIErrorKind::UnreadableFile(path.display().to_string(), e.to_string())
+                .spanned(&header),
+        )
+    })?;
+
+    let mut options = CHeaderOptions::default();
+    for (name, abi) in calling_conventions {
+        errors.collect(CFFIAnalyzer::validate_abi(&abi));
+        options
+            .calling_conventions
+            .insert(name.value(), abi.value());
+    }
+    options.ignore = ignore.iter().map(LitStr::value).collect();
+    let parsed = CFFIHeaderParser::parse(&source, &options);
+
+    let filter = CFFIHeaderFilter {
+        allow: allow
+            .iter()
+            .filter_map(|pattern| errors.collect(CFFIHeaderFilter::compile(pattern)))
+            .collect(),
+        block: block
+            .iter()
+            .filter_map(|pattern| errors.collect(CFFIHeaderFilter::compile(pattern)))
+            .collect(),
+    };
+
+    // 上書き指定された関数がヘッダーに存在するか検証する
+    for function_override in &overrides {
+        let name = function_override.ident.to_string();
+        if !parsed
+            .functions
+            .iter()
+            .any(|function| function.name == name)
+        {
+            let suggestion = CFFIAttributeAnalyzer::suggest(
+                &name,
+                parsed
+                    .functions
+                    .iter()
+                    .map(|function| function.name.as_str()),
+            );
+            errors.push(
+                CFFIErrorKind::UndeclaredHeaderFunction { name, suggestion }
+                    .spanned(&function_override.ident),
+            );
+        }
+    }
+
+    let functions = parsed
+        .functions
+        .iter()
+        .filter(|function| filter.is_allowed(&function.name))
+        .filter_map(|function| {
+            let attrs = overrides
+                .iter()
+                .filter(|function_override| function_override.ident == function.name)
+                .flat_map(|function_override| function_override.attrs.clone())
+                .collect::<Vec<_>>();
+            errors.collect(CFFIHeaderCodegen::function(function, attrs, &config_attrs))
+        })
+        .collect::<Vec<_>>();
+    let items = CFFIHeaderCodegen::items(&parsed, &filter);
+
+    errors.finish(())?;
+    let path = path.display().to_string();
+    let bindings = expand_cffi_gen(&config_attrs, &functions)?;
+    Ok(quote! {
+        // ヘッダーの変更時に再コンパイルさせる
+        const _: &[u8] = include_bytes!(#path);
+        #items
+        #bindings
+    })
+}
+
+// =====================================================================
+// #[derive(IntoFfiArg)]の実装を生成する関数
+// (Cとレイアウトが一致する#[repr(C)]、#[repr(transparent)]の型のみ値渡しを許可する)
+// (列挙型は#[repr(i32)]等の整数型の指定も可)
+// =====================================================================
+pub fn expand_derive_into_ffi_arg(input: &DeriveInput) -> Result<TokenStream, CFFIError> {
+    let mut reprs = Vec::new();
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if let Some(ident) = meta.path.get_ident() {
+                reprs.push(ident.to_string());
+            }
+            // #[repr(align(8))]等の値は読み飛ばす
+            if meta.input.peek(syn::token::Paren) {
+                meta.input.parse::<proc_macro2::TokenTree>()?;
+            }
+            Ok(())
+        })?;
+    }
+
+    let integer_reprs = [
+        "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize",
+    ];
+    let is_enum = matches!(input.data, Data::Enum(_));
+    let is_c_layout = reprs.iter().any(|repr| {
+        repr == "C" || repr == "transparent" || (is_enum && integer_reprs.contains(&repr.as_str()))
+    });
+    if !is_c_layout {
+        return Err(CFFIErrorKind::NotReprC(input.ident.to_string())
+            .spanned(&input.ident)
+            .into());
+    }
+    Ok(CFFICodegen::generate_into_ffi_arg_passthrough(
+        &input.ident,
+        &input.generics,
+    ))
+}
@@ -0,0 +1,1504 @@
+use std::collections::HashMap;
+
+// =====================================================================
+// Cヘッダーの字句
+// =====================================================================
+#[derive(Debug, Clone, PartialEq)]
+enum CToken {
+    Ident(String),
+    Number(String),
+    // 文字列リテラル(extern "C"の判定に使用)
+    Str(String),
+    Punct(char),
+    // 可変長引数(...)
+    Ellipsis,
+}
+
+// =====================================================================
+// Cの型
+// =====================================================================
+#[derive(Debug, Clone, PartialEq)]
+pub enum CType {
+    Void,
+    // int,unsigned long等の組み込み型(Rustの型のパス)
+    Primitive(&'static str),
+    // typedef名、もしくはヘッダー外で定義された型
+    Named(String),
+    // struct タグ名
+    Struct(String),
+    // enum タグ名(intとして扱う)
+    Enum,
+    Pointer {
+        pointee: Box<CType>,
+        // 指す先がconstか(const char* -> true)
+        is_const: bool,
+    },
+    // 配列(要素数は数値もしくは定数名)
+    Array(Box<CType>, String),
+    // 関数ポインタ
+    FnPtr {
+        ret: Box<CType>,
+        params: Vec<CType>,
+        abi: Option<String>,
+    },
+}
+
+// 引数名(省略されている場合はNone)と型
+pub type CParams = Vec<(Option<String>, CType)>;
+
+// =====================================================================
+// ヘッダーから読み取った関数宣言
+// =====================================================================
+pub struct CFunction {
+    pub name: String,
+    pub ret: CType,
+    pub params: CParams,
+    // 呼び出し規約(__stdcall等が指定されている場合のみ)
+    pub abi: Option<String>,
+}
+
+// =====================================================================
+// ヘッダーから読み取ったstruct
+// =====================================================================
+pub struct CStruct {
+    pub name: String,
+    pub fields: Vec<(String, CType)>,
+}
+
+// =====================================================================
+// ヘッダーから読み取ったenum(列挙子は値を計算したもの)
+// =====================================================================
+pub struct CEnum {
+    // typedef名、もしくはタグ名(無名の場合はNone)
+    pub name: Option<String>,
+    pub variants: Vec<(String, i128)>,
+}
+
+// =====================================================================
+// ヘッダー全体
+// =====================================================================
+#[derive(Default)]
+pub struct CHeader {
+    pub functions: Vec<CFunction>,
+    pub structs: Vec<CStruct>,
+    // 定義されずに参照のみされるstruct(不透明型として扱う)
+    pub opaque_structs: Vec<String>,
+    pub enums: Vec<CEnum>,
+    pub typedefs: Vec<(String, CType)>,
+    // #define NAME 整数 と値
+    pub constants: Vec<(String, i128)>,
+    // 解析できずに読み飛ばした宣言(可変長引数の関数を含む)
+    pub skipped: Vec<String>,
+}
+
+// =====================================================================
+// 宣言子(名前と型)
+// =====================================================================
+struct CDeclarator {
+    name: Option<String>,
+    ty: CType,
+    abi: Option<String>,
+    // 関数宣言の場合の引数(可変長引数の場合はNone)
+    params: Option<Option<CParams>>,
+}
+
+// =====================================================================
+// ヘッダーの解析設定
+// =====================================================================
+pub struct CHeaderOptions {
+    // 呼び出し規約として扱うマクロ名と対応するabi(__stdcall -> "system"等)
+    pub calling_conventions: HashMap<String, String>,
+    // 無視するマクロ名(DXLIBAPI,__declspec(dllimport)等)
+    pub ignore: Vec<String>,
+}
+
+impl Default for CHeaderOptions {
+    fn default() -> Self {
+        let calling_conventions = [
+            ("__cdecl", "C"),
+            ("_cdecl", "C"),
+            ("__stdcall", "system"),
+            ("_stdcall", "system"),
+            ("WINAPI", "system"),
+            ("APIENTRY", "system"),
+            ("CALLBACK", "system"),
+            ("__fastcall", "fastcall"),
+            ("__vectorcall", "vectorcall"),
+        ]
+        .into_iter()
+        .map(|(name, abi)| (name.to_string(), abi.to_string()))
+        .collect();
+        CHeaderOptions {
+            calling_conventions,
+            ignore: Vec::new(),
+        }
+    }
+}
+
+// 宣言の前後に付く、型に影響しない修飾子
+const C_QUALIFIERS: &[&str] = &[
+    "const",
+    "volatile",
+    "static",
+    "extern",
+    "inline",
+    "__inline",
+    "__inline__",
+    "__forceinline",
+    "register",
+    "restrict",
+    "__restrict",
+];
+
+// 組み込み型として扱う型名(typedef名として使用できるもの)とRustの型
+const C_NAMED_PRIMITIVES: &[(&str, &str)] = &[
+    ("size_t", "usize"),
+    ("ssize_t", "isize"),
+    ("ptrdiff_t", "isize"),
+    ("intptr_t", "isize"),
+    ("uintptr_t", "usize"),
+    ("int8_t", "i8"),
+    ("int16_t", "i16"),
+    ("int32_t", "i32"),
+    ("int64_t", "i64"),
+    ("uint8_t", "u8"),
+    ("uint16_t", "u16"),
+    ("uint32_t", "u32"),
+    ("uint64_t", "u64"),
+    ("bool", "bool"),
+    ("_Bool", "bool"),
+];
+
+// =====================================================================
+// Cヘッダー(実用的なサブセット)を解析する構造体
+// =====================================================================
+pub struct CFFIHeaderParser<'a> {
+    tokens: Vec<CToken>,
+    pos: usize,
+    options: &'a CHeaderOptions,
+    header: CHeader,
+    // 定義済みのstructタグ名
+    defined_structs: Vec<String>,
+}
+
+impl<'a> CFFIHeaderParser<'a> {
+    // =====================================================================
+    // ヘッダーの内容を解析する関数
+    // (解析できない宣言は読み飛ばす、#ifdef等の条件は考慮しない)
+    // =====================================================================
+    pub fn parse(source: &str, options: &'a CHeaderOptions) -> CHeader {
+        let (tokens, defines) = Self::tokenize(source);
+        let mut parser = CFFIHeaderParser {
+            tokens,
+            pos: 0,
+            options,
+            header: CHeader::default(),
+            defined_structs: Vec::new(),
+        };
+        parser.parse_defines(defines);
+        while parser.pos < parser.tokens.len() {
+            let start = parser.pos;
+            if parser.parse_top_level().is_none() {
+                parser.pos = start;
+                parser.skip_declaration();
+                parser.push_skipped(start);
+            }
+        }
+
+        // 名前の付かなかった無名のstruct(struct内の無名struct等)は対象外
+        parser.header.structs.retain(|st| !st.name.is_empty());
+
+        // 参照のみされるstructは不透明型とする
+        let mut referenced = Vec::new();
+        for function in &parser.header.functions {
+            Self::collect_structs(&function.ret, &mut referenced);
+            for (_, ty) in &function.params {
+                Self::collect_structs(ty, &mut referenced);
+            }
+        }
+        for (_, ty) in &parser.header.typedefs {
+            Self::collect_structs(ty, &mut referenced);
+        }
+        for st in &parser.header.structs {
+            for (_, ty) in &st.fields {
+                Self::collect_structs(ty, &mut referenced);
+            }
+        }
+        for name in referenced {
+            if !name.is_empty()
+                && !parser.defined_structs.contains(&name)
+                && !parser.header.opaque_structs.contains(&name)
+            {
+                parser.header.opaque_structs.push(name);
+            }
+        }
+        parser.header
+    }
+
+    // =====================================================================
+    // 型が参照するstructタグ名を集める関数
+    // =====================================================================
+    fn collect_structs(ty: &CType, names: &mut Vec<String>) {
+        match ty {
+            CType::Struct(name) => names.push(name.clone()),
+            CType::Pointer { pointee, .. } => Self::collect_structs(pointee, names),
+            CType::Array(elem, _) => Self::collect_structs(elem, names),
+            CType::FnPtr { ret, params, .. } => {
+                Self::collect_structs(ret, names);
+                params
+                    .iter()
+                    .for_each(|param| Self::collect_structs(param, names));
+            }
+            _ => {}
+        }
+    }
+
+    // =====================================================================
+    // 字句解析を行う関数(コメントを除去し、#defineは名前と値の組として返す)
+    // =====================================================================
+    fn tokenize(source: &str) -> (Vec<CToken>, Vec<(String, String)>) {
+        let chars = source.chars().collect::<Vec<_>>();
+        let mut tokens = Vec::new();
+        let mut defines = Vec::new();
+        let mut i = 0;
+        let mut line_start = true;
+        while i < chars.len() {
+            let c = chars[i];
+            if c == '\n' {
+                line_start = true;
+                i += 1;
+                continue;
+            }
+            if c.is_whitespace() {
+                i += 1;
+                continue;
+            }
+            // コメント
+            if c == '/' && chars.get(i + 1) == Some(&'/') {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            if c == '/' && chars.get(i + 1) == Some(&'*') {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            // プリプロセッサ指令(行末の\で継続する)
+            if c == '#' && line_start {
+                let mut directive = String::new();
+                i += 1;
+                while i < chars.len() && chars[i] != '\n' {
+                    if chars[i] == '\\' && chars.get(i + 1) == Some(&'\n') {
+                        i += 2;
+                        directive.push(' ');
+                        continue;
+                    }
+                    // 行末コメント
+                    if chars[i] == '/' && matches!(chars.get(i + 1), Some('/') | Some('*')) {
+                        break;
+                    }
+                    directive.push(chars[i]);
+                    i += 1;
+                }
+                if let Some(define) = Self::parse_define_directive(&directive) {
+                    defines.push(define);
+                }
+                continue;
+            }
+            line_start = false;
+
+            if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(CToken::Ident(chars[start..i].iter().collect()));
+            } else if c.is_ascii_digit() {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push(CToken::Number(chars[start..i].iter().collect()));
+            } else if c == '"' || c == '\'' {
+                let start = i + 1;
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                tokens.push(CToken::Str(
+                    chars[start..i.min(chars.len())].iter().collect(),
+                ));
+                i += 1;
+            } else if c == '.' && chars.get(i + 1) == Some(&'.') && chars.get(i + 2) == Some(&'.') {
+                tokens.push(CToken::Ellipsis);
+                i += 3;
+            } else {
+                tokens.push(CToken::Punct(c));
+                i += 1;
+            }
+        }
+        (tokens, defines)
+    }
+
+    // =====================================================================
+    // #define NAME value を名前と値に分ける関数(関数形式マクロは対象外)
+    // =====================================================================
+    fn parse_define_directive(directive: &str) -> Option<(String, String)> {
+        let rest = directive.trim_start().strip_prefix("define")?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let rest = rest.trim_start();
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (name, value) = rest.split_at(name_len);
+        if name.is_empty() || value.starts_with('(') {
+            return None;
+        }
+        Some((name.to_string(), value.trim().to_string()))
+    }
+
+    // =====================================================================
+    // #defineのうち、整数定数として計算できるものを定数とする関数
+    // =====================================================================
+    fn parse_defines(&mut self, defines: Vec<(String, String)>) {
+        for (name, value) in defines {
+            let (tokens, _) = Self::tokenize(&value);
+            if tokens.is_empty() {
+                continue;
+            }
+            let mut pos = 0;
+            if let Some(value) = self.eval_expr(&tokens, &mut pos, 0)
+                && pos == tokens.len()
+            {
+                self.header.constants.push((name, value));
+            }
+        }
+    }
+
+    // =====================================================================
+    // 整数の定数式を計算する関数(#define、enumの値に使用)
+    // (定義済みの定数、列挙子を参照可能)
+    // =====================================================================
+    fn eval_expr(&self, tokens: &[CToken], pos: &mut usize, min_prec: u8) -> Option<i128> {
+        let mut lhs = self.eval_unary(tokens, pos)?;
+        loop {
+            let (op, len) = match (tokens.get(*pos), tokens.get(*pos + 1)) {
+                (Some(CToken::Punct('<')), Some(CToken::Punct('<'))) => ("<<", 2),
+                (Some(CToken::Punct('>')), Some(CToken::Punct('>'))) => (">>", 2),
+                (Some(CToken::Punct(c)), _) if "+-*/%|&^".contains(*c) => (
+                    match c {
+                        '+' => "+",
+                        '-' => "-",
+                        '*' => "*",
+                        '/' => "/",
+                        '%' => "%",
+                        '|' => "|",
+                        '&' => "&",
+                        _ => "^",
+                    },
+                    1,
+                ),
+                _ => break,
+            };
+            let prec = match op {
+                "*" | "/" | "%" => 5,
+                "+" | "-" => 4,
+                "<<" | ">>" => 3,
+                "&" => 2,
+                "^" => 1,
+                _ => 0,
+            };
+            if prec < min_prec {
+                break;
+            }
+            *pos += len;
+            let rhs = self.eval_expr(tokens, pos, prec + 1)?;
+            lhs = match op {
+                "+" => lhs.checked_add(rhs)?,
+                "-" => lhs.checked_sub(rhs)?,
+                "*" => lhs.checked_mul(rhs)?,
+                "/" => lhs.checked_div(rhs)?,
+                "%" => lhs.checked_rem(rhs)?,
+                "<<" => lhs.checked_shl(u32::try_from(rhs).ok()?)?,
+                ">>" => lhs.checked_shr(u32::try_from(rhs).ok()?)?,
+                "&" => lhs & rhs,
+                "^" => lhs ^ rhs,
+                _ => lhs | rhs,
+            };
+        }
+        Some(lhs)
+    }
+
+    fn eval_unary(&self, tokens: &[CToken], pos: &mut usize) -> Option<i128> {
+        let token = tokens.get(*pos)?;
+        *pos += 1;
+        match token {
+            CToken::Punct('-') => self.eval_unary(tokens, pos)?.checked_neg(),
+            CToken::Punct('+') => self.eval_unary(tokens, pos),
+            CToken::Punct('~') => self.eval_unary(tokens, pos).map(|v| !v),
+            CToken::Punct('(') => {
+                let value = self.eval_expr(tokens, pos, 0)?;
+                (tokens.get(*pos) == Some(&CToken::Punct(')'))).then(|| *pos += 1)?;
+                Some(value)
+            }
+            CToken::Number(number) => Self::parse_number(number),
+            CToken::Ident(name) => self
+                .header
+                .constants
+                .iter()
+                .chain(self.header.enums.iter().flat_map(|e| &e.variants))
+                .find(|(known, _)| known == name)
+                .map(|(_, value)| *value),
+            _ => None,
+        }
+    }
+
+    // =====================================================================
+    // 整数リテラルを解析する関数(0x,0,0bのプレフィックス、u,lのサフィックスに対応)
+    // =====================================================================
+    fn parse_number(number: &str) -> Option<i128> {
+        let number = number.trim_end_matches(['u', 'U', 'l', 'L']);
+        if let Some(hex) = number
+            .strip_prefix("0x")
+            .or_else(|| number.strip_prefix("0X"))
+        {
+            i128::from_str_radix(hex, 16).ok()
+        } else if let Some(bin) = number
+            .strip_prefix("0b")
+            .or_else(|| number.strip_prefix("0B"))
+        {
+            i128::from_str_radix(bin, 2).ok()
+        } else if number.len() > 1 && number.starts_with('0') {
+            i128::from_str_radix(&number[1..], 8).ok()
+        } else {
+            number.parse().ok()
+        }
+    }
+
+    // =====================================================================
+    // トップレベルの宣言を1つ解析する関数
+    // =====================================================================
+    fn parse_top_level(&mut self) -> Option<()> {
+        match self.peek()? {
+            CToken::Punct(';') | CToken::Punct('}') => {
+                self.pos += 1;
+                Some(())
+            }
+            // extern "C" { ... } は中身をトップレベルとして扱う
+            CToken::Ident(ident) if ident == "extern" => {
+                if let Some(CToken::Str(_)) = self.tokens.get(self.pos + 1) {
+                    self.pos += 2;
+                    if self.peek() == Some(&CToken::Punct('{')) {
+                        self.pos += 1;
+                    }
+                    return Some(());
+                }
+                self.parse_declaration()
+            }
+            CToken::Ident(ident) if ident == "typedef" => {
+                self.pos += 1;
+                self.parse_typedef()
+            }
+            _ => self.parse_declaration(),
+        }
+    }
+
+    // =====================================================================
+    // typedef宣言を解析する関数
+    // =====================================================================
+    fn parse_typedef(&mut self) -> Option<()> {
+        let (mut base, base_const, mut tag) = self.parse_specifiers()?;
+        loop {
+            let declarator = self.parse_declarator(base.clone(), base_const)?;
+            let name = declarator.name?;
+            let mut ty = match declarator.params {
+                // typedef int FUNC(int); は関数ポインタとして扱う
+                Some(Some(params)) => CType::FnPtr {
+                    ret: Box::new(declarator.ty),
+                    params: params.into_iter().map(|(_, ty)| ty).collect(),
+                    abi: declarator.abi,
+                },
+                Some(None) => return None,
+                None => declarator.ty,
+            };
+
+            // 無名のstruct,enumには最初のtypedef名を付ける
+            // (typedef struct { ... } POINT, *LPPOINT; -> struct POINT, type LPPOINT = *mut POINT)
+            match tag.take() {
+                Some(CTag::AnonymousStruct(index)) => {
+                    let struct_name = if ty == base {
+                        name.clone()
+                    } else {
+                        format!("{name}_Target")
+                    };
+                    self.header.structs[index].name = struct_name.clone();
+                    self.defined_structs.push(struct_name.clone());
+                    base = CType::Struct(struct_name);
+                    ty = Self::name_anonymous(ty, &base);
+                }
+                Some(CTag::AnonymousEnum(index)) if ty == base => {
+                    self.header.enums[index].name = Some(name.clone());
+                }
+                _ => {}
+            }
+            self.push_typedef(name, ty);
+            match self.next()? {
+                CToken::Punct(',') => continue,
+                CToken::Punct(';') => return Some(()),
+                _ => return None,
+            }
+        }
+    }
+
+    // =====================================================================
+    // 型に含まれる無名のstructを指定の型に置き換える関数
+    // =====================================================================
+    fn name_anonymous(ty: CType, named: &CType) -> CType {
+        match ty {
+            CType::Struct(name) if name.is_empty() => named.clone(),
+            CType::Pointer { pointee, is_const } => CType::Pointer {
+                pointee: Box::new(Self::name_anonymous(*pointee, named)),
+                is_const,
+            },
+            CType::Array(elem, dim) => {
+                CType::Array(Box::new(Self::name_anonymous(*elem, named)), dim)
+            }
+            ty => ty,
+        }
+    }
+
+    // =====================================================================
+    // typedefを登録する関数
+    // =====================================================================
+    fn push_typedef(&mut self, name: String, ty: CType) {
+        // 組み込み型として扱う型名(size_t等)、struct X と同名のtypedef(typedef struct X X;)、
+        // 名前を付けたenum自体は登録しない
+        if C_NAMED_PRIMITIVES.iter().any(|(known, _)| *known == name)
+            || ty == CType::Struct(name.clone())
+            || self
+                .header
+                .enums
+                .iter()
+                .any(|e| e.name.as_ref() == Some(&name))
+        {
+            return;
+        }
+        self.header.typedefs.push((name, ty));
+    }
+
+    // =====================================================================
+    // 関数宣言等の宣言を解析する関数(変数宣言は読み飛ばす)
+    // =====================================================================
+    fn parse_declaration(&mut self) -> Option<()> {
+        let start = self.pos;
+        let (base, base_const, _) = self.parse_specifiers()?;
+        if self.peek() == Some(&CToken::Punct(';')) {
+            self.pos += 1;
+            return Some(());
+        }
+        let mut variadic = false;
+        loop {
+            let declarator = self.parse_declarator(base.clone(), base_const)?;
+            if let (Some(name), Some(params)) = (declarator.name, declarator.params) {
+                match params {
+                    Some(params) if !self.header.functions.iter().any(|f| f.name == name) => {
+                        self.header.functions.push(CFunction {
+                            name,
+                            ret: declarator.ty,
+                            params,
+                            abi: declarator.abi,
+                        });
+                    }
+                    Some(_) => {}
+                    // 可変長引数の関数は対象外(読み飛ばした宣言として記録する)
+                    None => variadic = true,
+                }
+            }
+            match self.next()? {
+                CToken::Punct(',') => continue,
+                CToken::Punct(';') => {}
+                // インライン関数の本体
+                CToken::Punct('{') => {
+                    self.pos -= 1;
+                    self.skip_balanced();
+                }
+                _ => return None,
+            }
+            if variadic {
+                self.push_skipped(start);
+            }
+            return Some(());
+        }
+    }
+
+    // =====================================================================
+    // 型指定子を解析する関数
+    // (基本の型、基本の型がconstか、struct/enumの定義があればそのタグを返す)
+    // =====================================================================
+    fn parse_specifiers(&mut self) -> Option<(CType, bool, Option<CTag>)> {
+        let mut is_const = false;
+        let mut words: Vec<String> = Vec::new();
+        let mut named = None;
+        let mut tag = None;
+        while let Some(CToken::Ident(ident)) = self.peek().cloned() {
+            if ident == "const" {
+                is_const = true;
+                self.pos += 1;
+            } else if C_QUALIFIERS.contains(&ident.as_str()) || self.is_ignored(&ident) {
+                self.pos += 1;
+                self.skip_macro_args(&ident);
+            } else if matches!(
+                ident.as_str(),
+                "void"
+                    | "char"
+                    | "short"
+                    | "int"
+                    | "long"
+                    | "float"
+                    | "double"
+                    | "signed"
+                    | "unsigned"
+            ) {
+                // DXLIBAPI int f(); のような未知のマクロは読み飛ばしたものとする
+                if matches!(named, Some(CType::Named(_))) {
+                    named = None;
+                }
+                words.push(ident);
+                self.pos += 1;
+            } else if ident == "struct" || ident == "union" || ident == "enum" {
+                if !words.is_empty() || named.is_some() {
+                    break;
+                }
+                self.pos += 1;
+                let (ty, defined) = self.parse_tagged(&ident)?;
+                named = Some(ty);
+                tag = defined;
+            } else if words.is_empty() && named.is_none() {
+                named = Some(
+                    match C_NAMED_PRIMITIVES.iter().find(|(known, _)| *known == ident) {
+                        Some((_, rust_ty)) => CType::Primitive(rust_ty),
+                        None => CType::Named(ident),
+                    },
+                );
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let ty = match named {
+            Some(ty) => ty,
+            None => Self::primitive(&words)?,
+        };
+        Some((ty, is_const, tag))
+    }
+
+    // =====================================================================
+    // 組み込み型の組み合わせ(unsigned long int等)をRustの型にする関数
+    // =====================================================================
+    fn primitive(words: &[String]) -> Option<CType> {
+        let has = |word: &str| words.iter().any(|w| w == word);
+        let longs = words.iter().filter(|w| *w == "long").count();
+        let unsigned = has("unsigned");
+        let ty = if has("void") {
+            return Some(CType::Void);
+        } else if has("float") {
+            "f32"
+        } else if has("double") {
+            "f64"
+        } else if has("char") {
+            match (unsigned, has("signed")) {
+                (true, _) => "::std::os::raw::c_uchar",
+                (false, true) => "::std::os::raw::c_schar",
+                (false, false) => "::std::os::raw::c_char",
+            }
+        } else if has("short") {
+            if unsigned {
+                "::std::os::raw::c_ushort"
+            } else {
+                "::std::os::raw::c_short"
+            }
+        } else if longs >= 2 {
+            if unsigned {
+                "::std::os::raw::c_ulonglong"
+            } else {
+                "::std::os::raw::c_longlong"
+            }
+        } else if longs == 1 {
+            if unsigned {
+                "::std::os::raw::c_ulong"
+            } else {
+                "::std::os::raw::c_long"
+            }
+        } else if has("int") || has("signed") || unsigned {
+            if unsigned {
+                "::std::os::raw::c_uint"
+            } else {
+                "::std::os::raw::c_int"
+            }
+        } else {
+            return None;
+        };
+        Some(CType::Primitive(ty))
+    }
+
+    // =====================================================================
+    // struct/union/enum X { ... } を解析する関数
+    // =====================================================================
+    fn parse_tagged(&mut self, keyword: &str) -> Option<(CType, Option<CTag>)> {
+        let name = match self.peek() {
+            Some(CToken::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Some(name)
+            }
+            _ => None,
+        };
+        let has_body = self.peek() == Some(&CToken::Punct('{'));
+        match keyword {
+            "enum" => {
+                if !has_body {
+                    return Some((CType::Enum, None));
+                }
+                let index = self.parse_enum_body(name.clone())?;
+                let tag = name.is_none().then_some(CTag::AnonymousEnum(index));
+                Some((CType::Enum, tag))
+            }
+            // unionは未対応(中身を読み飛ばし、不透明型として扱う)
+            "union" => {
+                if has_body {
+                    self.skip_balanced();
+                }
+                Some((CType::Struct(name?), None))
+            }
+            _ => {
+                if !has_body {
+                    return Some((CType::Struct(name?), None));
+                }
+                let fields = self.parse_struct_body()?;
+                let index = self.header.structs.len();
+                self.header.structs.push(CStruct {
+                    name: name.clone().unwrap_or_default(),
+                    fields,
+                });
+                match name {
+                    Some(name) => {
+                        self.defined_structs.push(name.clone());
+                        Some((CType::Struct(name), None))
+                    }
+                    None => Some((
+                        CType::Struct(String::new()),
+                        Some(CTag::AnonymousStruct(index)),
+                    )),
+                }
+            }
+        }
+    }
+
+    // =====================================================================
+    // structのフィールドを解析する関数
+    // =====================================================================
+    fn parse_struct_body(&mut self) -> Option<Vec<(String, CType)>> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        while self.peek() != Some(&CToken::Punct('}')) {
+            let (base, base_const, _) = self.parse_specifiers()?;
+            loop {
+                let declarator = self.parse_declarator(base.clone(), base_const)?;
+                // ビットフィールドは未対応
+                if self.peek() == Some(&CToken::Punct(':')) {
+                    return None;
+                }
+                fields.push((declarator.name?, declarator.ty));
+                match self.next()? {
+                    CToken::Punct(',') => continue,
+                    CToken::Punct(';') => break,
+                    _ => return None,
+                }
+            }
+        }
+        self.expect('}')?;
+        Some(fields)
+    }
+
+    // =====================================================================
+    // enumの列挙子を解析する関数(値を省略した場合は前の値 + 1)
+    // =====================================================================
+    fn parse_enum_body(&mut self, name: Option<String>) -> Option<usize> {
+        self.expect('{')?;
+        let index = self.header.enums.len();
+        self.header.enums.push(CEnum {
+            name,
+            variants: Vec::new(),
+        });
+        let mut next_value = 0;
+        loop {
+            let variant = match self.next()? {
+                CToken::Ident(variant) => variant,
+                CToken::Punct('}') => return Some(index),
+                _ => return None,
+            };
+            if self.peek() == Some(&CToken::Punct('=')) {
+                self.pos += 1;
+                let start = self.pos;
+                let mut depth = 0;
+                while let Some(token) = self.peek() {
+                    match token {
+                        CToken::Punct('(') => depth += 1,
+                        CToken::Punct(')') => depth -= 1,
+                        CToken::Punct(',') | CToken::Punct('}') if depth == 0 => break,
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+                let tokens = self.tokens[start..self.pos].to_vec();
+                let mut pos = 0;
+                next_value = self.eval_expr(&tokens, &mut pos, 0)?;
+            }
+            self.header.enums[index]
+                .variants
+                .push((variant, next_value));
+            next_value += 1;
+            if self.peek() == Some(&CToken::Punct(',')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    // =====================================================================
+    // 宣言子(ポインタ、名前、配列、関数引数)を解析する関数
+    // =====================================================================
+    fn parse_declarator(&mut self, base: CType, base_const: bool) -> Option<CDeclarator> {
+        let mut ty = base;
+        let mut pointee_const = base_const;
+        let mut abi = None;
+        loop {
+            match self.peek() {
+                Some(CToken::Punct('*')) => {
+                    ty = CType::Pointer {
+                        pointee: Box::new(ty),
+                        is_const: pointee_const,
+                    };
+                    pointee_const = false;
+                    self.pos += 1;
+                }
+                Some(CToken::Ident(ident)) if C_QUALIFIERS.contains(&ident.as_str()) => {
+                    self.pos += 1;
+                }
+                Some(CToken::Ident(ident))
+                    if self.options.calling_conventions.contains_key(ident) =>
+                {
+                    abi = self.options.calling_conventions.get(ident).cloned();
+                    self.pos += 1;
+                }
+                Some(CToken::Ident(ident)) if self.is_ignored(ident) => {
+                    let ident = ident.clone();
+                    self.pos += 1;
+                    self.skip_macro_args(&ident);
+                }
+                _ => break,
+            }
+        }
+
+        // 関数ポインタ ret (CALLCONV *name)(params)
+        if self.peek() == Some(&CToken::Punct('(')) {
+            let start = self.pos;
+            self.pos += 1;
+            let mut fn_abi = None;
+            while let Some(CToken::Ident(ident)) = self.peek() {
+                if let Some(conv) = self.options.calling_conventions.get(ident) {
+                    fn_abi = Some(conv.clone());
+                }
+                self.pos += 1;
+                if self.peek() == Some(&CToken::Punct('*')) {
+                    break;
+                }
+            }
+            if self.peek() == Some(&CToken::Punct('*')) {
+                self.pos += 1;
+                let name = match self.peek() {
+                    Some(CToken::Ident(name)) => {
+                        let name = name.clone();
+                        self.pos += 1;
+                        Some(name)
+                    }
+                    _ => None,
+                };
+                self.expect(')')?;
+                let params = self.parse_params()??;
+                let ty = CType::FnPtr {
+                    ret: Box::new(ty),
+                    params: params.into_iter().map(|(_, ty)| ty).collect(),
+                    abi: fn_abi,
+                };
+                return Some(CDeclarator {
+                    name,
+                    ty,
+                    abi: None,
+                    params: None,
+                });
+            }
+            // 名前のない関数型の引数等
+            self.pos = start;
+        }
+
+        let name = match self.peek() {
+            Some(CToken::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Some(name)
+            }
+            _ => None,
+        };
+
+        let mut params = None;
+        if self.peek() == Some(&CToken::Punct('(')) {
+            params = Some(self.parse_params()?);
+        }
+        // 配列(多次元配列は外側から)
+        let mut dims = Vec::new();
+        while self.peek() == Some(&CToken::Punct('[')) {
+            self.pos += 1;
+            let dim = match self.next()? {
+                CToken::Number(number) => {
+                    self.expect(']')?;
+                    Self::parse_number(&number)?.to_string()
+                }
+                CToken::Ident(name) => {
+                    self.expect(']')?;
+                    name
+                }
+                CToken::Punct(']') => String::new(),
+                _ => return None,
+            };
+            dims.push(dim);
+        }
+        for dim in dims.into_iter().rev() {
+            ty = if dim.is_empty() {
+                CType::Pointer {
+                    pointee: Box::new(ty),
+                    is_const: false,
+                }
+            } else {
+                CType::Array(Box::new(ty), dim)
+            };
+        }
+        Some(CDeclarator {
+            name,
+            ty,
+            abi,
+            params,
+        })
+    }
+
+    // =====================================================================
+    // 関数の引数を解析する関数(可変長引数の場合はSome(None))
+    // =====================================================================
+    fn parse_params(&mut self) -> Option<Option<CParams>> {
+        self.expect('(')?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        // f(void)
+        if self.peek() == Some(&CToken::Ident("void".to_string()))
+            && self.tokens.get(self.pos + 1) == Some(&CToken::Punct(')'))
+        {
+            self.pos += 2;
+            return Some(Some(params));
+        }
+        while self.peek() != Some(&CToken::Punct(')')) {
+            if self.peek() == Some(&CToken::Ellipsis) {
+                self.pos += 1;
+                variadic = true;
+                continue;
+            }
+            let (base, base_const, _) = self.parse_specifiers()?;
+            let declarator = self.parse_declarator(base, base_const)?;
+            // 引数の配列はポインタとして扱う
+            let ty = match declarator.ty {
+                CType::Array(elem, _) => CType::Pointer {
+                    pointee: elem,
+                    is_const: base_const,
+                },
+                ty => ty,
+            };
+            params.push((declarator.name, ty));
+            if self.peek() == Some(&CToken::Punct(',')) {
+                self.pos += 1;
+            }
+        }
+        self.expect(')')?;
+        Some((!variadic).then_some(params))
+    }
+
+    // =====================================================================
+    // 無視するマクロか判定する関数
+    // =====================================================================
+    fn is_ignored(&self, ident: &str) -> bool {
+        ident == "__declspec"
+            || ident == "__attribute__"
+            || self.options.ignore.iter().any(|i| i == ident)
+    }
+
+    // =====================================================================
+    // __declspec(dllimport)等の引数を読み飛ばす関数
+    // =====================================================================
+    fn skip_macro_args(&mut self, ident: &str) {
+        if self.is_ignored(ident) && self.peek() == Some(&CToken::Punct('(')) {
+            self.skip_balanced();
+        }
+    }
+
+    // =====================================================================
+    // 括弧の対応を取りながら読み飛ばす関数
+    // =====================================================================
+    fn skip_balanced(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token {
+                CToken::Punct('(') | CToken::Punct('{') | CToken::Punct('[') => depth += 1,
+                CToken::Punct(')') | CToken::Punct('}') | CToken::Punct(']') => {
+                    depth -= 1;
+                    if depth <= 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // =====================================================================
+    // 解析できない宣言を次の;まで読み飛ばす関数
+    // =====================================================================
+    fn skip_declaration(&mut self) {
+        let mut depth = 0;
+        while let Some(token) = self.next() {
+            match token {
+                CToken::Punct('(') | CToken::Punct('{') | CToken::Punct('[') => depth += 1,
+                CToken::Punct(')') | CToken::Punct(']') => depth -= 1,
+                CToken::Punct('}') => {
+                    depth -= 1;
+                    // 本体付きの関数定義等
+                    if depth == 0 && self.peek() != Some(&CToken::Punct(';')) {
+                        return;
+                    }
+                }
+                CToken::Punct(';') if depth <= 0 => return,
+                _ => {}
+            }
+        }
+    }
+
+    // =====================================================================
+    // 指定位置から現在位置までの宣言を、読み飛ばした宣言として記録する関数
+    // =====================================================================
+    fn push_skipped(&mut self, start: usize) {
+        let tokens = &self.tokens[start..self.pos.min(self.tokens.len())];
+        let text = tokens
+            .iter()
+            .map(|token| match token {
+                CToken::Ident(text) | CToken::Number(text) => text.clone(),
+                CToken::Str(text) => format!("{text:?}"),
+                CToken::Punct(c) => c.to_string(),
+                CToken::Ellipsis => "...".to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !text.is_empty() {
+            self.header.skipped.push(text);
+        }
+    }
+
+    fn peek(&self) -> Option<&CToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<CToken> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, punct: char) -> Option<()> {
+        (self.next()? == CToken::Punct(punct)).then_some(())
+    }
+}
+
+// =====================================================================
+// typedefで名前を付ける無名のstruct,enum(ヘッダー内の位置)
+// =====================================================================
+enum CTag {
+    AnonymousStruct(usize),
+    AnonymousEnum(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> CHeader {
+        CFFIHeaderParser::parse(source, &CHeaderOptions::default())
+    }
+
+    fn ptr(pointee: CType, is_const: bool) -> CType {
+        CType::Pointer {
+            pointee: Box::new(pointee),
+            is_const,
+        }
+    }
+
+    type Pairs<'a, T> = &'a [(&'a str, T)];
+    // (ソース, struct名とフィールド, 不透明型)
+    type StructCase<'a> = (&'a str, Pairs<'a, Pairs<'a, CType>>, &'a [&'a str]);
+
+    const C_INT: CType = CType::Primitive("::std::os::raw::c_int");
+    const C_CHAR: CType = CType::Primitive("::std::os::raw::c_char");
+
+    #[test]
+    fn test_tokenize() {
+        use CToken::*;
+        let ident = |s: &str| Ident(s.to_string());
+        let cases: &[(&str, Vec<CToken>, Pairs<'_, &str>)] = &[
+            ("int x;", vec![ident("int"), ident("x"), Punct(';')], &[]),
+            // コメントは除去する
+            (
+                "a /* b */ c // d\ne",
+                vec![ident("a"), ident("c"), ident("e")],
+                &[],
+            ),
+            (
+                "0x1Fu 'c' \"C\" ...",
+                vec![
+                    Number("0x1Fu".to_string()),
+                    Str("c".to_string()),
+                    Str("C".to_string()),
+                    Ellipsis,
+                ],
+                &[],
+            ),
+            // #defineは名前と値の組にする(行末の\で継続、行末コメントは除く)
+            (
+                "#define A 1 // one\n#define B (A + \\\n 2)\nx",
+                vec![ident("x")],
+                &[("A", "1"), ("B", "(A +   2)")],
+            ),
+            // 関数形式マクロ、#define以外の指令は対象外
+            ("#define F(x) x\n#include <a.h>\n#ifdef X\n", vec![], &[]),
+        ];
+        for (source, tokens, defines) in cases {
+            let (actual_tokens, actual_defines) = CFFIHeaderParser::tokenize(source);
+            assert_eq!(&actual_tokens, tokens, "{source}");
+            let defines = defines
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>();
+            assert_eq!(actual_defines, defines, "{source}");
+        }
+    }
+
+    #[test]
+    fn test_defines() {
+        let cases: &[(&str, Option<i128>)] = &[
+            ("42", Some(42)),
+            ("0x10", Some(16)),
+            ("010", Some(8)),
+            ("0b101", Some(5)),
+            ("100UL", Some(100)),
+            ("(1 << 4) | 1", Some(17)),
+            ("2 + 3 * 4", Some(14)),
+            ("(2 + 3) * 4", Some(20)),
+            ("-(3)", Some(-3)),
+            ("~0", Some(-1)),
+            ("BASE + 1", Some(11)),
+            ("1 << 70", Some(1 << 70)),
+            // 計算できない値は定数としない
+            ("1 / 0", None),
+            ("1 << 200", None),
+            ("-(-170141183460469231731687303715884105727 - 1)", None),
+            ("\"text\"", None),
+            ("UNKNOWN", None),
+            ("(1", None),
+        ];
+        for (value, expected) in cases {
+            let header = parse(&format!("#define BASE 10\n#define VALUE {value}\n"));
+            let actual = header
+                .constants
+                .iter()
+                .find(|(name, _)| name == "VALUE")
+                .map(|(_, value)| *value);
+            assert_eq!(actual, *expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_typedef() {
+        let point = CType::Struct("POINT".to_string());
+        let cases: &[(&str, Pairs<'_, CType>)] = &[
+            ("typedef int INT;", &[("INT", C_INT)]),
+            (
+                "typedef const char *LPCSTR;",
+                &[("LPCSTR", ptr(C_CHAR, true))],
+            ),
+            (
+                "typedef size_t SIZE;",
+                &[("SIZE", CType::Primitive("usize"))],
+            ),
+            (
+                "typedef unsigned long long U64, *PU64;",
+                &[
+                    ("U64", CType::Primitive("::std::os::raw::c_ulonglong")),
+                    (
+                        "PU64",
+                        ptr(CType::Primitive("::std::os::raw::c_ulonglong"), false),
+                    ),
+                ],
+            ),
+            // 無名のstructには最初のtypedef名を付ける
+            (
+                "typedef struct { int x; } POINT, *LPPOINT;",
+                &[("LPPOINT", ptr(point.clone(), false))],
+            ),
+            (
+                "typedef struct HANDLE_ *HANDLE;",
+                &[("HANDLE", ptr(CType::Struct("HANDLE_".to_string()), false))],
+            ),
+            // typedef struct X X; とsize_t等は登録しない
+            ("typedef struct X X; typedef unsigned int size_t;", &[]),
+        ];
+        for (source, expected) in cases {
+            let header = parse(source);
+            let expected = expected
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(header.typedefs, expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn test_struct() {
+        let cases: &[StructCase] = &[
+            (
+                "struct POINT { int x, y; };",
+                &[("POINT", &[("x", C_INT), ("y", C_INT)])],
+                &[],
+            ),
+            (
+                "typedef struct { char name[MAX_PATH]; const char *label; } ITEM;",
+                &[(
+                    "ITEM",
+                    &[
+                        (
+                            "name",
+                            CType::Array(Box::new(C_CHAR), "MAX_PATH".to_string()),
+                        ),
+                        ("label", ptr(C_CHAR, true)),
+                    ],
+                )],
+                &[],
+            ),
+            // 多次元配列は外側から
+            (
+                "struct M { float m[2][3]; };",
+                &[(
+                    "M",
+                    &[(
+                        "m",
+                        CType::Array(
+                            Box::new(CType::Array(
+                                Box::new(CType::Primitive("f32")),
+                                "3".to_string(),
+                            )),
+                            "2".to_string(),
+                        ),
+                    )],
+                )],
+                &[],
+            ),
+            // 参照のみされるstructは不透明型
+            (
+                "struct NODE { struct NODE *next; struct DATA *data; };",
+                &[(
+                    "NODE",
+                    &[
+                        ("next", ptr(CType::Struct("NODE".to_string()), false)),
+                        ("data", ptr(CType::Struct("DATA".to_string()), false)),
+                    ],
+                )],
+                &["DATA"],
+            ),
+            // ビットフィールドは未対応
+            ("struct FLAGS { int a : 1; };", &[], &[]),
+        ];
+        for (source, structs, opaque) in cases {
+            let header = parse(source);
+            let actual = header
+                .structs
+                .iter()
+                .map(|st| (st.name.as_str(), st.fields.clone()))
+                .collect::<Vec<_>>();
+            let expected = structs
+                .iter()
+                .map(|(name, fields)| {
+                    let fields = fields
+                        .iter()
+                        .map(|(name, ty)| (name.to_string(), ty.clone()))
+                        .collect::<Vec<_>>();
+                    (*name, fields)
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected, "{source}");
+            assert_eq!(header.opaque_structs, *opaque, "{source}");
+        }
+    }
+
+    #[test]
+    fn test_enum() {
+        let cases: &[(&str, Option<&str>, Pairs<'_, i128>)] = &[
+            (
+                "enum { A, B = 5, C };",
+                None,
+                &[("A", 0), ("B", 5), ("C", 6)],
+            ),
+            (
+                "enum COLOR { RED = 1, GREEN = RED << 1, BLUE = (GREEN | RED), };",
+                Some("COLOR"),
+                &[("RED", 1), ("GREEN", 2), ("BLUE", 3)],
+            ),
+            // 無名のenumにはtypedef名を付ける
+            ("typedef enum { X = -1 } SIGN;", Some("SIGN"), &[("X", -1)]),
+        ];
+        for (source, name, variants) in cases {
+            let header = parse(source);
+            assert_eq!(header.enums.len(), 1, "{source}");
+            assert_eq!(header.enums[0].name.as_deref(), *name, "{source}");
+            let expected = variants
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect::<Vec<_>>();
+            assert_eq!(header.enums[0].variants, expected, "{source}");
+            assert!(header.typedefs.is_empty(), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_fn_pointer() {
+        let compare = CType::FnPtr {
+            ret: Box::new(C_INT),
+            params: vec![ptr(CType::Void, true), ptr(CType::Void, true)],
+            abi: None,
+        };
+        let cases: &[(&str, CType)] = &[
+            (
+                "typedef int (*COMPARE)(const void *, const void *);",
+                compare.clone(),
+            ),
+            // typedef int FUNC(...); も関数ポインタとして扱う
+            (
+                "typedef int COMPARE(const void *a, const void *b);",
+                compare,
+            ),
+            (
+                "typedef void (__stdcall *COMPARE)(void);",
+                CType::FnPtr {
+                    ret: Box::new(CType::Void),
+                    params: Vec::new(),
+                    abi: Some("system".to_string()),
+                },
+            ),
+        ];
+        for (source, ty) in cases {
+            let header = parse(source);
+            assert_eq!(
+                header.typedefs,
+                vec![("COMPARE".to_string(), ty.clone())],
+                "{source}"
+            );
+        }
+
+        // 関数の引数の関数ポインタ
+        let header = parse("void sort(void *base, int (*compar)(int));");
+        assert_eq!(
+            header.functions[0].params[1],
+            (
+                Some("compar".to_string()),
+                CType::FnPtr {
+                    ret: Box::new(C_INT),
+                    params: vec![C_INT],
+                    abi: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        let mut options = CHeaderOptions::default();
+        options.ignore.push("DXLIBAPI".to_string());
+        let cases: &[(&str, &str, Option<&str>, usize)] = &[
+            ("int f(void);", "f", None, 0),
+            ("extern int f(int a, char *b);", "f", None, 2),
+            // 呼び出し規約のマクロ
+            ("int __stdcall f(int);", "f", Some("system"), 1),
+            ("int WINAPI f(int);", "f", Some("system"), 1),
+            ("int __cdecl f(int);", "f", Some("C"), 1),
+            ("void __fastcall f(void);", "f", Some("fastcall"), 0),
+            // 無視するマクロ
+            (
+                "__declspec(dllimport) int __stdcall f(int);",
+                "f",
+                Some("system"),
+                1,
+            ),
+            ("DXLIBAPI int f(int x[4]);", "f", None, 1),
+            ("extern \"C\" { int f(void); }", "f", None, 0),
+            ("static inline int f(int a) { return a; }", "f", None, 1),
+        ];
+        for (source, name, abi, params) in cases {
+            let header = CFFIHeaderParser::parse(source, &options);
+            assert_eq!(header.functions.len(), 1, "{source}");
+            let function = &header.functions[0];
+            assert_eq!(function.name, *name, "{source}");
+            assert_eq!(function.abi.as_deref(), *abi, "{source}");
+            assert_eq!(function.params.len(), *params, "{source}");
+            assert!(header.skipped.is_empty(), "{source}");
+        }
+
+        // 引数の配列はポインタとして扱う
+        let header = CFFIHeaderParser::parse("DXLIBAPI int f(int x[4]);", &options);
+        assert_eq!(header.functions[0].params[0].1, ptr(C_INT, false));
+    }
+
+    #[test]
+    fn test_skipped() {
+        let header = parse(
+            "int printf(const char *format, ...);\n\
+             struct FLAGS { int a : 1; };\n\
+             int ok(void);",
+        );
+        assert_eq!(
+            header
+                .functions
+                .iter()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            ["ok"]
+        );
+        assert_eq!(
+            header.skipped,
+            [
+                "int printf ( const char * format , ... ) ;",
+                "struct FLAGS { int a : 1 ; } ;",
+            ]
+        );
+    }
+}
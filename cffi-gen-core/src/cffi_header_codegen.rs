@@ -0,0 +1,355 @@
+use crate::cffi_attribute_analyzer::*;
+use crate::cffi_codegen::*;
+use crate::cffi_error::*;
+use crate::cffi_header::*;
+use crate::defines::*;
+use crate::structs::*;
+use proc_macro2::{Literal, Span};
+use quote::{format_ident, quote};
+use regex_lite::Regex;
+use std::collections::HashSet;
+use syn::{Attribute, Generics, Ident, LitStr, parse_quote};
+
+// =====================================================================
+// allow{},block{}による名前の絞り込み
+// =====================================================================
+pub struct CFFIHeaderFilter {
+    pub allow: Vec<Regex>,
+    pub block: Vec<Regex>,
+}
+
+impl CFFIHeaderFilter {
+    // =====================================================================
+    // パターンを名前全体に一致する正規表現にする関数("DxLib_.*" -> ^(?:DxLib_.*)$)
+    // =====================================================================
+    pub fn compile(pattern: &LitStr) -> syn::Result<Regex> {
+        Regex::new(&format!("^(?:{})$", pattern.value())).map_err(|e| {
+            CFFIErrorKind::InvalidPattern(pattern.value(), e.to_string()).spanned(pattern)
+        })
+    }
+
+    // =====================================================================
+    // 名前が対象か判定する関数(allow{}が空の場合は全て対象、block{}が優先)
+    // =====================================================================
+    pub fn is_allowed(&self, name: &str) -> bool {
+        (self.allow.is_empty() || self.allow.iter().any(|re| re.is_match(name)))
+            && !self.is_blocked(name)
+    }
+
+    pub fn is_blocked(&self, name: &str) -> bool {
+        self.block.iter().any(|re| re.is_match(name))
+    }
+}
+
+// =====================================================================
+// ヘッダーの内容からRustのコードを生成する構造体
+// =====================================================================
+pub struct CFFIHeaderCodegen;
+
+impl CFFIHeaderCodegen {
+    // =====================================================================
+    // ヘッダーの関数宣言をcffi_gen!の関数宣言にする関数
+    // (上書き指定のアトリビュートを優先し、呼び出し規約は#[abi]として付与する)
+    // =====================================================================
+    pub fn function(
+        function: &CFunction,
+        mut attrs: Vec<Attribute>,
+        config_attrs: &[Attribute],
+    ) -> syn::Result<FunctionWithAttrs> {
+        if let Some(abi) = &function.abi {
+            attrs.push(parse_quote! { #[abi = #abi] });
+        }
+        let ident = match syn::parse_str::<Ident>(&function.name) {
+            Ok(ident) => ident,
+            // Rustのキーワードと同名の関数は名前を変え、シンボル名を指定する
+            Err(_) => {
+                let name = &function.name;
+                attrs.push(parse_quote! { #[func_name = #name] });
+                format_ident!("{}_", function.name)
+            }
+        };
+
+        // arg_convertが有効な場合、const char*を&strとして受け取る
+        let arg_convert = CFFIAttributeAnalyzer::resolve(
+            &attrs,
+            config_attrs,
+            M_ATTR_ARG_CONVERT,
+            CFFIAttributeAnalyzer::get_bool,
+        )?
+        .unwrap_or(false);
+        let params = function.params.iter().enumerate().map(|(i, (name, ty))| {
+            let name = match name {
+                Some(name) if syn::parse_str::<Ident>(name).is_ok() => format_ident!("{}", name),
+                Some(name) => format_ident!("{}_", name),
+                None => format_ident!("arg{}", i),
+            };
+            let ty = match ty {
+                CType::Pointer {
+                    pointee,
+                    is_const: true,
+                } if arg_convert && **pointee == CType::Primitive("::std::os::raw::c_char") => {
+                    quote! { &str }
+                }
+                ty => Self::rust_type(ty),
+            };
+            quote! { #name: #ty }
+        });
+        let output = match &function.ret {
+            CType::Void => quote! {},
+            ty => {
+                let ty = Self::rust_type(ty);
+                quote! { -> #ty }
+            }
+        };
+        let sig = syn::parse2(quote! { fn #ident(#(#params),*) #output })?;
+        Ok(FunctionWithAttrs {
+            attrs,
+            vis: None,
+            sig,
+        })
+    }
+
+    // =====================================================================
+    // 型、定数の定義を生成する関数
+    // (関数、struct、typedefから参照される型は、allow{}に含まれなくても生成する)
+    // =====================================================================
+    pub fn items(header: &CHeader, filter: &CFFIHeaderFilter) -> proc_macro2::TokenStream {
+        let needed = Self::needed_types(header, filter);
+        let is_type_emitted = |name: &str| {
+            !filter.is_blocked(name) && (filter.is_allowed(name) || needed.contains(name))
+        };
+        let allow_lints = quote! {
+            #[allow(non_camel_case_types, non_upper_case_globals, non_snake_case, dead_code)]
+        };
+        let c_int = quote! { ::std::os::raw::c_int };
+        let mut output = quote! {};
+
+        for (name, value) in &header.constants {
+            if !filter.is_allowed(name) {
+                continue;
+            }
+            let ident = format_ident!("{}", name);
+            let (ty, value) = Self::int_literal(*value);
+            output.extend(quote! {
+                #allow_lints
+                pub const #ident: #ty = #value;
+            });
+        }
+
+        for c_enum in &header.enums {
+            let ty = match &c_enum.name {
+                Some(name) if is_type_emitted(name) => {
+                    let ident = format_ident!("{}", name);
+                    output.extend(quote! {
+                        #allow_lints
+                        pub type #ident = #c_int;
+                    });
+                    quote! { #ident }
+                }
+                Some(_) => continue,
+                None => c_int.clone(),
+            };
+            for (variant, value) in &c_enum.variants {
+                if c_enum.name.is_none() && !filter.is_allowed(variant) {
+                    continue;
+                }
+                let ident = format_ident!("{}", variant);
+                let value = Literal::i128_unsuffixed(*value);
+                output.extend(quote! {
+                    #allow_lints
+                    pub const #ident: #ty = #value;
+                });
+            }
+        }
+
+        for c_struct in &header.structs {
+            if !is_type_emitted(&c_struct.name) {
+                continue;
+            }
+            let ident = format_ident!("{}", c_struct.name);
+            let fields = c_struct.fields.iter().map(|(name, ty)| {
+                let name = Self::field_ident(name);
+                let ty = Self::rust_type(ty);
+                quote! { pub #name: #ty }
+            });
+            output.extend(quote! {
+                #allow_lints
+                #[repr(C)]
+                #[derive(Debug, Clone, Copy)]
+                pub struct #ident {
+                    #(#fields,)*
+                }
+            });
+            output.extend(CFFICodegen::generate_into_ffi_arg_passthrough(
+                &ident,
+                &Generics::default(),
+            ));
+        }
+
+        // 中身が分からないstructはポインタでのみ使用する不透明型とする
+        for name in &header.opaque_structs {
+            if !is_type_emitted(name) {
+                continue;
+            }
+            let ident = format_ident!("{}", name);
+            output.extend(quote! {
+                #allow_lints
+                #[repr(C)]
+                pub struct #ident {
+                    _private: [u8; 0],
+                }
+            });
+        }
+
+        for (name, ty) in &header.typedefs {
+            if !is_type_emitted(name) {
+                continue;
+            }
+            let ident = format_ident!("{}", name);
+            let ty = Self::rust_type(ty);
+            output.extend(quote! {
+                #allow_lints
+                pub type #ident = #ty;
+            });
+        }
+        output
+    }
+
+    // =====================================================================
+    // 生成する関数から参照される型名を集める関数(typedef、structを辿る)
+    // =====================================================================
+    fn needed_types(header: &CHeader, filter: &CFFIHeaderFilter) -> HashSet<String> {
+        let mut pending = Vec::new();
+        for function in &header.functions {
+            if !filter.is_allowed(&function.name) {
+                continue;
+            }
+            Self::collect_names(&function.ret, &mut pending);
+            for (_, ty) in &function.params {
+                Self::collect_names(ty, &mut pending);
+            }
+        }
+
+        let mut needed = HashSet::new();
+        while let Some(name) = pending.pop() {
+            if filter.is_blocked(&name) || !needed.insert(name.clone()) {
+                continue;
+            }
+            for (typedef, ty) in &header.typedefs {
+                if *typedef == name {
+                    Self::collect_names(ty, &mut pending);
+                }
+            }
+            for c_struct in &header.structs {
+                if c_struct.name == name {
+                    for (_, ty) in &c_struct.fields {
+                        Self::collect_names(ty, &mut pending);
+                    }
+                }
+            }
+        }
+        needed
+    }
+
+    fn collect_names(ty: &CType, names: &mut Vec<String>) {
+        match ty {
+            CType::Named(name) | CType::Struct(name) => names.push(name.clone()),
+            CType::Pointer { pointee, .. } => Self::collect_names(pointee, names),
+            CType::Array(elem, dim) => {
+                Self::collect_names(elem, names);
+                names.push(dim.clone());
+            }
+            CType::FnPtr { ret, params, .. } => {
+                Self::collect_names(ret, names);
+                params
+                    .iter()
+                    .for_each(|param| Self::collect_names(param, names));
+            }
+            _ => {}
+        }
+    }
+
+    // =====================================================================
+    // Cの型をRustの型にする関数
+    // =====================================================================
+    fn rust_type(ty: &CType) -> proc_macro2::TokenStream {
+        match ty {
+            CType::Void => quote! { () },
+            CType::Primitive(path) => {
+                let path: syn::Type = syn::parse_str(path).expect("primitive type path");
+                quote! { #path }
+            }
+            CType::Named(name) | CType::Struct(name) => {
+                let ident = format_ident!("{}", name);
+                quote! { #ident }
+            }
+            CType::Enum => quote! { ::std::os::raw::c_int },
+            CType::Pointer { pointee, is_const } => {
+                let pointee = match &**pointee {
+                    CType::Void => quote! { ::std::ffi::c_void },
+                    pointee => Self::rust_type(pointee),
+                };
+                if *is_const {
+                    quote! { *const #pointee }
+                } else {
+                    quote! { *mut #pointee }
+                }
+            }
+            CType::Array(elem, dim) => {
+                let elem = Self::rust_type(elem);
+                match dim.parse::<usize>() {
+                    Ok(len) => {
+                        let len = Literal::usize_unsuffixed(len);
+                        quote! { [#elem; #len] }
+                    }
+                    Err(_) => {
+                        let ident = format_ident!("{}", dim);
+                        quote! { [#elem; #ident as usize] }
+                    }
+                }
+            }
+            CType::FnPtr { ret, params, abi } => {
+                let abi = LitStr::new(abi.as_deref().unwrap_or(DEFAULT_ABI), Span::call_site());
+                let params = params.iter().map(Self::rust_type);
+                let output = match &**ret {
+                    CType::Void => quote! {},
+                    ret => {
+                        let ret = Self::rust_type(ret);
+                        quote! { -> #ret }
+                    }
+                };
+                quote! {
+                    ::std::option::Option<unsafe extern #abi fn(#(#params),*) #output>
+                }
+            }
+        }
+    }
+
+    // =====================================================================
+    // 定数の値から型と値を決定する関数(i32に収まらない場合はu32,i64,u64,i128)
+    // =====================================================================
+    fn int_literal(value: i128) -> (proc_macro2::TokenStream, Literal) {
+        let ty = if i32::try_from(value).is_ok() {
+            quote! { i32 }
+        } else if u32::try_from(value).is_ok() {
+            quote! { u32 }
+        } else if i64::try_from(value).is_ok() {
+            quote! { i64 }
+        } else if u64::try_from(value).is_ok() {
+            quote! { u64 }
+        } else {
+            quote! { i128 }
+        };
+        (ty, Literal::i128_unsuffixed(value))
+    }
+
+    // =====================================================================
+    // フィールド名がキーワードの場合にr#を付ける関数
+    // =====================================================================
+    fn field_ident(name: &str) -> Ident {
+        match syn::parse_str::<Ident>(name) {
+            Ok(ident) => ident,
+            Err(_) => Ident::new_raw(name, Span::call_site()),
+        }
+    }
+}
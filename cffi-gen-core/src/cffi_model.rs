@@ -0,0 +1,1245 @@
+use crate::cffi_analyzer::*;
+use crate::cffi_attribute_analyzer::*;
+use crate::cffi_def_file::*;
+use crate::cffi_error::*;
+use crate::defines::*;
+use crate::structs::*;
+use proc_macro2::{Span, TokenStream};
+use quote::{ToTokens, format_ident, quote};
+use std::path::PathBuf;
+use syn::visit_mut::VisitMut;
+use syn::{
+    Attribute, Expr, ExprCall, FnArg, Generics, Ident, Lifetime, LitInt, LitStr, Meta, Pat,
+    PatType, PathArguments, ReturnType, Type, TypeImplTrait, TypeParamBound, Visibility,
+};
+
+// =====================================================================
+// cffi_gen!,#[cffi_module],#[cffi]で共通して使用する中間表現
+// (各マクロは入力からCFFIModelを構築し、CFFICodegenでコードを生成する)
+// =====================================================================
+pub struct CFFIModel {
+    pub library: LibrarySpec,
+    pub functions: Vec<FunctionSpec>,
+    // #[def_file = value]で指定された.defファイルのパス(変更時に再コンパイルさせるため)
+    pub def_file_path: Option<PathBuf>,
+    // .defファイルのEXPORTSのうち、宣言されていないもの(#[def_report_undeclared]指定時のみ)
+    pub undeclared_exports: Option<Vec<String>>,
+}
+
+// =====================================================================
+// ライブラリ単位の設定
+// =====================================================================
+pub struct LibrarySpec {
+    // #[library_name = value]
+    pub name: Option<String>,
+    // #[link_type = value]
+    pub link_type: Option<String>,
+    // #[link_cfg = value]のcfgの述語(指定がない場合は常にリンク指定を行う)
+    pub link_cfg: Option<Meta>,
+    // #[link_type = "runtime"]の場合の設定(Noneの場合はリンク時に解決する)
+    pub runtime: Option<RuntimeSpec>,
+}
+
+// =====================================================================
+// 実行時に共有ライブラリを読み込む場合の設定
+// =====================================================================
+pub struct RuntimeSpec {
+    // 生成するライブラリ構造体名(ライブラリ名のパスカルケース + Library)
+    pub struct_name: Ident,
+    // グローバルインスタンスを保持するstatic変数名
+    pub global_name: Ident,
+    // 読み込み時に全てのシンボルを解決するか(#[symbol_resolution = "eager"])
+    pub eager: bool,
+}
+
+// =====================================================================
+// 関数単位の設定(config{}の設定を引き継ぎ、関数単位の指定で上書きしたもの)
+// =====================================================================
+pub struct FunctionSpec {
+    // 使用者向けに生成されるラップ関数名
+    pub wrapper_name: Ident,
+    // ラップ関数の可視性
+    pub vis: Visibility,
+    // リンク時に使用する関数名
+    pub link_name: String,
+    // 呼び出し規約
+    pub abi: LitStr,
+    pub generics: Generics,
+    pub args: Vec<ArgSpec>,
+    pub ret: ReturnSpec,
+    pub error_policy: ErrorPolicy,
+    // #[optional]の場合、シンボルを初回呼び出し時に解決する
+    pub optional: bool,
+    // ラップ関数にそのまま付与するアトリビュート(#[doc],#[cfg]等)
+    pub attrs: Vec<Attribute>,
+}
+
+// =====================================================================
+// 引数単位の設定
+// =====================================================================
+pub struct ArgSpec {
+    pub ident: Ident,
+    // ラップ関数側の引数の型
+    pub wrapper_ty: Type,
+    // extern宣言側の引数の型
+    pub extern_ty: TokenStream,
+    // ラップ関数の引数からextern宣言の引数への変換方法
+    pub conversion: ArgConversion,
+}
+
+// =====================================================================
+// 引数の変換方法
+// =====================================================================
+pub enum ArgConversion {
+    // そのまま渡す
+    PassThrough,
+    // Option<T> -> T (Noneの場合は指定の式)
+    OptionDefault(TokenStream),
+    // #[callback(...)] impl FnMut(..) -> T をトランポリンの関数ポインタとして渡す
+    Callback(CallbackSpec),
+    // #[callback(userdata = value)]で指定された引数
+    // (ラップ関数の引数にせず、クロージャの状態を指すポインタを渡す)
+    CallbackUserdata,
+    // IntoFfiArg::into_ffi(source)で変換し、ガードを呼び出しの間保持する
+    // (mutableの場合、sourceが引数を&mutで借用するため可変で束縛し直す)
+    IntoFfi { source: TokenStream, mutable: bool },
+    // #[as_arg_type = T] TryFrom(Intoが実装されている場合も含む)でTに変換して渡す
+    TryInto(Type),
+    // #[as_arg_type(lossy = T)] asでTに変換して渡す(値のこぼれは考慮しない)
+    Lossy(Type),
+}
+
+// =====================================================================
+// #[callback(...)]の設定
+// =====================================================================
+pub struct CallbackSpec {
+    // クロージャのトレイト境界(FnMut(i32) -> i32等)
+    pub bound: TokenStream,
+    // クロージャの引数の型
+    pub inputs: Vec<Type>,
+    // クロージャの戻り値
+    pub output: ReturnType,
+    // トランポリンの呼び出し規約(指定がない場合は関数と同じ)
+    pub abi: LitStr,
+    // クロージャの状態をトランポリンに渡す方法
+    pub context: CallbackContext,
+    // 呼び出し後もC側で保持される場合はtrue(CallbackRegistrationで所有権を返す)
+    pub registered: bool,
+}
+
+// =====================================================================
+// トランポリンからクロージャを見つける方法
+// =====================================================================
+pub enum CallbackContext {
+    // userdata = value 指定の引数(void*)でクロージャの状態を渡す
+    // (positionはC側のコールバックでuserdataを受け取る引数の位置、指定がない場合は最後)
+    Userdata { arg: Ident, position: usize },
+    // static_slots = N userdataがない場合に、N個のトランポリンとスロットのテーブルを使用する
+    StaticSlots(usize),
+}
+
+// =====================================================================
+// 戻り値の設定
+// =====================================================================
+pub struct ReturnSpec {
+    // extern宣言側の戻り値
+    pub output: ReturnType,
+    // ラップ関数側の戻り値の型(戻り値がない場合は())
+    pub ty: Type,
+}
+
+// =====================================================================
+// エラー時の扱い
+// =====================================================================
+pub struct ErrorPolicy {
+    // 戻り値をResultに変換するか
+    pub as_result: bool,
+    // resultという名前で戻り値を比較するエラー条件(Noneの場合はエラー判定を行わない)
+    pub condition: Option<TokenStream>,
+    // Resultのエラー型
+    pub error_type: TokenStream,
+    // エラー時に返すエラー値
+    pub error_value: TokenStream,
+    // エラーに含める引数を変換前に保持する文(#[error_with_args]指定時のみ)
+    // (Copyと分かる型は値のコピー、それ以外は文字列にしたものを保持する)
+    pub args_capture: Option<TokenStream>,
+}
+
+impl CFFIModel {
+    // =====================================================================
+    // config属性と関数宣言群からモデルを構築する関数
+    // =====================================================================
+    pub fn build<'a>(
+        config_attrs: &[Attribute],
+        fns: impl IntoIterator<Item = &'a FunctionWithAttrs>,
+    ) -> Result<Self, CFFIError> {
+        let mut errors = CFFIError::new();
+        errors.collect(CFFIAttributeAnalyzer::validate(
+            config_attrs,
+            CFFIAttributeLevel::Config,
+        ));
+        let library = errors.collect(LibrarySpec::build(config_attrs));
+        let def_file = errors
+            .collect(CFFIAttributeAnalyzer::get_str(
+                config_attrs,
+                M_ATTR_DEF_FILE,
+            ))
+            .flatten()
+            .and_then(|def_file| errors.collect(CFFIDefFile::load(&def_file)));
+        let report_undeclared = errors
+            .collect(CFFIAttributeAnalyzer::get_bool(
+                config_attrs,
+                M_ATTR_DEF_REPORT_UNDECLARED,
+            ))
+            .flatten()
+            .unwrap_or(false);
+
+        // 全ての関数のエラーを収集する(.defファイルの指定がある場合はシンボルの存在も検証する)
+        let functions: Vec<FunctionSpec> = fns
+            .into_iter()
+            .filter_map(|function| {
+                let spec = errors.collect(FunctionSpec::build(config_attrs, function))?;
+                if let Some(def_file) = &def_file {
+                    errors.collect(def_file.validate(&spec.link_name, function.sig.ident.span()));
+                }
+                Some(spec)
+            })
+            .collect();
+
+        // ライブラリ構造体のメソッドとラップ関数名が衝突する場合はエラーとする
+        if library
+            .as_ref()
+            .is_some_and(|library| library.runtime.is_some())
+        {
+            for function in &functions {
+                let wrapper_name = function.wrapper_name.to_string();
+                if RUNTIME_RESERVED_NAMES.contains(&wrapper_name.as_str()) {
+                    errors.push(
+                        CFFIErrorKind::ReservedRuntimeName(wrapper_name)
+                            .spanned(&function.wrapper_name),
+                    );
+                }
+            }
+        }
+
+        // #[optional]の関数に生成される{ラップ関数名}_is_availableと、ラップ関数名が衝突する場合はエラーとする
+        for optional in functions.iter().filter(|function| function.optional) {
+            let available_name = format!("{}_is_available", optional.wrapper_name);
+            for function in &functions {
+                if function.wrapper_name == available_name {
+                    errors.push(
+                        CFFIErrorKind::AvailableNameConflict {
+                            name: available_name.clone(),
+                            function: optional.wrapper_name.to_string(),
+                        }
+                        .spanned(&function.wrapper_name),
+                    );
+                }
+            }
+        }
+
+        let undeclared_exports = def_file
+            .as_ref()
+            .filter(|_| report_undeclared)
+            .map(|def_file| {
+                def_file.undeclared(functions.iter().map(|function| function.link_name.as_str()))
+            });
+        match library {
+            Some(library) => errors.finish(CFFIModel {
+                library,
+                functions,
+                def_file_path: def_file.map(|def_file| def_file.path),
+                undeclared_exports,
+            }),
+            None => Err(errors),
+        }
+    }
+}
+
+impl LibrarySpec {
+    pub fn build(config_attrs: &[Attribute]) -> syn::Result<Self> {
+        let name = CFFIAttributeAnalyzer::get_str(config_attrs, M_ATTR_LIBRARY_NAME)?;
+        let link_type = CFFIAttributeAnalyzer::get_str(config_attrs, M_ATTR_LIBRARY_LINK_TYPE)?;
+        let link_cfg = CFFIAttributeAnalyzer::get_str(config_attrs, M_ATTR_LINK_CFG)?
+            .map(|link_cfg| {
+                link_cfg.parse::<Meta>().map_err(|_| {
+                    CFFIErrorKind::InvalidAttributeValue(
+                        M_ATTR_LINK_CFG,
+                        "a cfg predicate such as \"windows\" or \"target_os = \\\"windows\\\"\"",
+                    )
+                    .spanned(&link_cfg)
+                })
+            })
+            .transpose()?;
+
+        let runtime = match &link_type {
+            Some(link_type) if link_type.value() == LINK_TYPE_RUNTIME => {
+                let Some(name) = &name else {
+                    return Err(CFFIErrorKind::RuntimeWithoutLibraryName.spanned(link_type));
+                };
+                Some(RuntimeSpec::build(config_attrs, &name.value())?)
+            }
+            _ => None,
+        };
+
+        Ok(LibrarySpec {
+            name: name.map(|name| name.value()),
+            link_type: link_type.map(|link_type| link_type.value()),
+            link_cfg,
+            runtime,
+        })
+    }
+}
+
+impl RuntimeSpec {
+    pub fn build(config_attrs: &[Attribute], library_name: &str) -> syn::Result<Self> {
+        // 識別子に使用できない文字は区切りとして扱う("DxLib_x64" -> DxLibX64、"SDL2-2.0" -> SDL220)
+        let sanitized = library_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        let mut pascal = CFFIAnalyzer::to_pascal_case(&sanitized);
+        if !pascal.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            pascal.insert(0, 'L');
+        }
+        let struct_name = format_ident!("{}Library", pascal);
+        let global_name = format_ident!("__CFFI_GLOBAL_{}", struct_name.to_string().to_uppercase());
+
+        // 指定がない場合は初回呼び出し時に解決する
+        let resolution = CFFIAttributeAnalyzer::get_str(config_attrs, M_ATTR_SYMBOL_RESOLUTION)?;
+        let eager = match &resolution {
+            Some(resolution) if resolution.value() == "eager" => true,
+            Some(resolution) if resolution.value() == "lazy" => false,
+            Some(resolution) => {
+                return Err(CFFIErrorKind::InvalidAttributeValue(
+                    M_ATTR_SYMBOL_RESOLUTION,
+                    "\"eager\" or \"lazy\"",
+                )
+                .spanned(resolution));
+            }
+            None => false,
+        };
+
+        Ok(RuntimeSpec {
+            struct_name,
+            global_name,
+            eager,
+        })
+    }
+}
+
+impl FunctionSpec {
+    // =====================================================================
+    // 関数名に#[strip_prefix]、#[rename_all]を適用したラップ関数名を返す関数
+    // =====================================================================
+    fn renamed_ident(
+        config_attrs: &[Attribute],
+        attrs: &[Attribute],
+        ident: &Ident,
+    ) -> syn::Result<Ident> {
+        let strip_prefix = CFFIAttributeAnalyzer::resolve(
+            attrs,
+            config_attrs,
+            M_ATTR_STRIP_PREFIX,
+            CFFIAttributeAnalyzer::get_str,
+        )?;
+        let rename_all = CFFIAttributeAnalyzer::resolve(
+            attrs,
+            config_attrs,
+            M_ATTR_RENAME_ALL,
+            CFFIAttributeAnalyzer::get_str,
+        )?;
+        if strip_prefix.is_none() && rename_all.is_none() {
+            return Ok(ident.clone());
+        }
+
+        let original = ident.to_string();
+        let mut name = match &strip_prefix {
+            Some(prefix) => original
+                .strip_prefix(&prefix.value())
+                .unwrap_or(&original)
+                .to_string(),
+            None => original.clone(),
+        };
+        if let Some(rule) = &rename_all {
+            name = CFFIAnalyzer::apply_rename_rule(&name, &rule.value()).ok_or_else(|| {
+                CFFIErrorKind::InvalidAttributeValue(
+                    M_ATTR_RENAME_ALL,
+                    "one of \"snake_case\", \"camelCase\", \"PascalCase\", \"SCREAMING_SNAKE_CASE\", \"lowercase\", \"UPPERCASE\"",
+                )
+                .spanned(rule)
+            })?;
+        }
+
+        // 変換後の名前がキーワードの場合はr#を付ける(`Loop` -> r#loop)
+        if syn::parse_str::<Ident>(&name).is_ok() {
+            Ok(Ident::new(&name, ident.span()))
+        } else if syn::parse_str::<Ident>(&format!("r#{name}")).is_ok() {
+            Ok(Ident::new_raw(&name, ident.span()))
+        } else {
+            Err(CFFIErrorKind::InvalidIdentifier(name).spanned(ident))
+        }
+    }
+
+    pub fn build(
+        config_attrs: &[Attribute],
+        function: &FunctionWithAttrs,
+    ) -> Result<Self, CFFIError> {
+        let FunctionWithAttrs { attrs, vis, sig } = function;
+        let mut errors = CFFIError::new();
+        errors.collect(CFFIAttributeAnalyzer::validate(
+            attrs,
+            CFFIAttributeLevel::Function,
+        ));
+
+        // #[func_alias = value]もしくは省略形の#[alias = value]
+        let alias =
+            CFFIAttributeAnalyzer::get_ident(attrs, M_ATTR_FUNC_ALIAS).and_then(
+                |alias| match alias {
+                    Some(alias) => Ok(Some(alias)),
+                    None => CFFIAttributeAnalyzer::get_ident(attrs, M_ATTR_FUNC_ALIAS_SHORT),
+                },
+            );
+        // #[func_alias]の指定がない場合は、#[strip_prefix]、#[rename_all]を適用した名前とする
+        let wrapper_name = match errors.collect(alias).flatten() {
+            Some(alias) => alias,
+            None => errors
+                .collect(Self::renamed_ident(config_attrs, attrs, &sig.ident))
+                .unwrap_or_else(|| sig.ident.clone()),
+        };
+
+        // 関数単位で指定がある場合、呼び出し規約を上書き(指定がない場合は"C")
+        let abi = errors
+            .collect(CFFIAttributeAnalyzer::resolve(
+                attrs,
+                config_attrs,
+                M_ATTR_ABI,
+                CFFIAttributeAnalyzer::get_str,
+            ))
+            .flatten()
+            .unwrap_or_else(|| LitStr::new(DEFAULT_ABI, Span::call_site()));
+        errors.collect(CFFIAnalyzer::validate_abi(&abi));
+
+        // 関数単位で指定がある場合、arg_convertの値を上書き(指定がない場合は変換しない)
+        let arg_convert = errors
+            .collect(CFFIAttributeAnalyzer::resolve(
+                attrs,
+                config_attrs,
+                M_ATTR_ARG_CONVERT,
+                CFFIAttributeAnalyzer::get_bool,
+            ))
+            .flatten()
+            .unwrap_or(false);
+
+        // 関数単位で指定がある場合、as_resultの値を上書き(指定がない場合は変換しない)
+        let as_result = errors
+            .collect(CFFIAttributeAnalyzer::resolve(
+                attrs,
+                config_attrs,
+                M_ATTR_AS_RESULT,
+                CFFIAttributeAnalyzer::get_bool,
+            ))
+            .flatten()
+            .unwrap_or(false);
+
+        // 関数単位で指定がある場合、プレフィックス指定を上書き
+        let top_prefix = errors
+            .collect(CFFIAttributeAnalyzer::resolve(
+                attrs,
+                config_attrs,
+                M_ATTR_FUNC_NAME_TOP_PREFIX,
+                CFFIAttributeAnalyzer::get_str,
+            ))
+            .flatten()
+            .map(|prefix| prefix.value())
+            .unwrap_or_default();
+        let down_prefix = errors
+            .collect(CFFIAttributeAnalyzer::resolve(
+                attrs,
+                config_attrs,
+                M_ATTR_FUNC_NAME_DOWN_PREFIX,
+                CFFIAttributeAnalyzer::get_str,
+            ))
+            .flatten()
+            .map(|prefix| prefix.value())
+            .unwrap_or_default();
+        // #[func_name = value]の場合、プレフィックスを付けずにそのままシンボル名とする
+        // (識別子として使用できない`_Foo@8`,`?Foo@@YAHXZ`等も指定可能)
+        let func_name = errors
+            .collect(CFFIAttributeAnalyzer::get_str(attrs, M_ATTR_FUNC_NAME))
+            .flatten();
+        if let Some(func_name) = &func_name
+            && (func_name.value().is_empty() || func_name.value().contains('\0'))
+        {
+            errors.push(
+                CFFIErrorKind::InvalidAttributeValue(
+                    M_ATTR_FUNC_NAME,
+                    "a non-empty symbol name without NUL",
+                )
+                .spanned(func_name),
+            );
+        }
+        let link_name = if let Some(func_name) = &func_name {
+            func_name.value()
+        } else {
+            // プレフィックスは関数名の前、ダウンプレフィックスは関数名の後ろに付ける(併用可能)
+            format!("{}{}{}", top_prefix, sig.ident, down_prefix)
+        };
+
+        // 全ての引数のエラーを収集する
+        let mut args: Vec<ArgSpec> = sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                FnArg::Typed(pat_type) => {
+                    errors.collect(CFFIAttributeAnalyzer::validate(
+                        &pat_type.attrs,
+                        CFFIAttributeLevel::Argument,
+                    ));
+                    errors.collect(ArgSpec::build(pat_type, arg_convert, &abi))
+                }
+                FnArg::Receiver(receiver) => {
+                    errors.push(CFFIErrorKind::UnsupportedReceiver.spanned(receiver));
+                    None
+                }
+            })
+            .collect();
+        ArgSpec::link_callback_userdata(&mut args, &mut errors);
+
+        // #[optional]の場合、リンク時に解決しない(指定がない場合はリンク時に解決する)
+        let optional = errors
+            .collect(CFFIAttributeAnalyzer::get_bool(attrs, M_ATTR_OPTIONAL))
+            .flatten()
+            .unwrap_or(false);
+
+        let ret = ReturnSpec::build(&sig.output);
+        let error_policy = errors.collect(ErrorPolicy::build(
+            &wrapper_name,
+            &link_name,
+            &args,
+            config_attrs,
+            attrs,
+            as_result,
+            &ret,
+        ));
+
+        let Some(error_policy) = error_policy else {
+            return Err(errors);
+        };
+        errors.finish(FunctionSpec {
+            wrapper_name,
+            vis: vis.clone().unwrap_or_else(|| syn::parse_quote! { pub }),
+            link_name,
+            abi,
+            generics: sig.generics.clone(),
+            args,
+            ret,
+            error_policy,
+            optional,
+            attrs: attrs
+                .iter()
+                .filter(|attr| {
+                    PASSTHROUGH_ATTRIBUTES
+                        .iter()
+                        .any(|name| attr.path().is_ident(name))
+                })
+                .cloned()
+                .collect(),
+        })
+    }
+}
+
+impl ArgSpec {
+    // =====================================================================
+    // 引数の型から変換方法を決定する関数
+    // =====================================================================
+    pub fn build(pat_type: &PatType, arg_convert: bool, abi: &LitStr) -> syn::Result<Self> {
+        let PatType { attrs, pat, ty, .. } = pat_type;
+        let ident = match &**pat {
+            Pat::Ident(pi) => pi.ident.clone(),
+            _ => return Err(CFFIErrorKind::UnsupportedPatternArgument.spanned(pat)),
+        };
+        let ty = (**ty).clone();
+
+        // #[callback]はarg_convertの指定に関わらず、クロージャをトランポリン経由で渡す
+        if let Some(attr) = attrs
+            .iter()
+            .find(|attr| attr.path().is_ident(M_ATTR_CALLBACK))
+        {
+            let callback = CallbackSpec::build(attr, &ty, abi)?;
+            // 登録したクロージャは呼び出し後も別スレッド等から呼ばれうるため、Send + 'staticとする
+            // (static_slotsの場合もstaticのテーブルで保持するため同様)
+            let shared =
+                callback.registered || matches!(callback.context, CallbackContext::StaticSlots(_));
+            let wrapper_ty = match (&ty, shared) {
+                (Type::ImplTrait(impl_trait), true) => {
+                    let mut impl_trait = impl_trait.clone();
+                    impl_trait
+                        .bounds
+                        .push(syn::parse_quote! { ::std::marker::Send });
+                    impl_trait.bounds.push(syn::parse_quote! { 'static });
+                    Type::ImplTrait(impl_trait)
+                }
+                _ => ty,
+            };
+            return Ok(ArgSpec {
+                ident,
+                wrapper_ty,
+                extern_ty: callback.fn_type(),
+                conversion: ArgConversion::Callback(callback),
+            });
+        }
+
+        // #[as_arg_type]はarg_convertの指定に関わらず、他の変換より優先する
+        let (extern_ty, conversion) = if let Some(resolved) = Self::resolve_as_arg_type(attrs)? {
+            resolved
+        } else if arg_convert {
+            Self::resolve_conversion(&ident, &ty, attrs)?
+        } else {
+            (quote! { #ty }, ArgConversion::PassThrough)
+        };
+
+        Ok(ArgSpec {
+            ident,
+            wrapper_ty: ty,
+            extern_ty,
+            conversion,
+        })
+    }
+
+    // =====================================================================
+    // #[callback(userdata = value)]で指定された引数を、userdataを受け取る引数にする関数
+    // =====================================================================
+    fn link_callback_userdata(args: &mut [ArgSpec], errors: &mut CFFIError) {
+        let userdata_idents = args
+            .iter()
+            .filter_map(|arg| match &arg.conversion {
+                ArgConversion::Callback(CallbackSpec {
+                    context: CallbackContext::Userdata { arg, .. },
+                    ..
+                }) => Some(arg.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (i, userdata) in userdata_idents.iter().enumerate() {
+            if userdata_idents[..i].contains(userdata) {
+                errors.push(
+                    CFFIErrorKind::InvalidCallback(format!(
+                        "`{userdata}` is already used as the userdata of another callback"
+                    ))
+                    .spanned(userdata),
+                );
+                continue;
+            }
+            match args.iter_mut().find(|arg| arg.ident == *userdata) {
+                Some(arg) if matches!(arg.conversion, ArgConversion::Callback(_)) => errors.push(
+                    CFFIErrorKind::InvalidCallback(format!(
+                        "`{userdata}` is a callback and cannot receive the userdata"
+                    ))
+                    .spanned(userdata),
+                ),
+                Some(arg) => arg.conversion = ArgConversion::CallbackUserdata,
+                None => errors.push(
+                    CFFIErrorKind::InvalidCallback(format!(
+                        "`{userdata}` is not an argument of this function"
+                    ))
+                    .spanned(userdata),
+                ),
+            }
+        }
+    }
+
+    // =====================================================================
+    // #[as_arg_type = T]、#[as_arg_type(lossy = T)]から変換方法を決定する関数
+    // (extern宣言側の型と変換方法を返す)
+    // =====================================================================
+    fn resolve_as_arg_type(
+        attrs: &[Attribute],
+    ) -> syn::Result<Option<(TokenStream, ArgConversion)>> {
+        if let Some(CFFIAttribute {
+            value: CFFIAttributeValue::Expr(Expr::Assign(assign)),
+            ..
+        }) = CFFIAttributeAnalyzer::find(attrs, M_ATTR_AS_ARG_TYPE)?
+            && matches!(&*assign.left, Expr::Path(p) if p.path.is_ident("lossy"))
+        {
+            let right = &assign.right;
+            let target = syn::parse2::<Type>(quote! { #right }).map_err(|e| {
+                CFFIErrorKind::InvalidType(M_ATTR_AS_ARG_TYPE, e.to_string()).spanned(right)
+            })?;
+            return Ok(Some((quote! { #target }, ArgConversion::Lossy(target))));
+        }
+        let target = CFFIAttributeAnalyzer::get_type(attrs, M_ATTR_AS_ARG_TYPE)?;
+        Ok(target.map(|target| (quote! { #target }, ArgConversion::TryInto(target))))
+    }
+
+    // =====================================================================
+    // arg_convert = default の場合の変換方法を決定する関数
+    // (extern宣言側の型と変換方法を返す)
+    // =====================================================================
+    fn resolve_conversion(
+        ident: &Ident,
+        ty: &Type,
+        attrs: &[Attribute],
+    ) -> syn::Result<(TokenStream, ArgConversion)> {
+        // Option<&T>、Option<&mut T>はIntoFfiArgでポインタに変換する(Noneの場合はnull)
+        if let Some(inner_ty) = CFFIAnalyzer::is_option(ty)
+            && matches!(inner_ty, Type::Reference(_))
+        {
+            return Self::resolve_into_ffi_conversion(ident, ty);
+        }
+
+        // Option<T> -> T
+        if let Some(inner_ty) = CFFIAnalyzer::is_option(ty) {
+            let default_expr = Self::resolve_option_default(attrs)?;
+            return Ok((
+                quote! { #inner_ty },
+                ArgConversion::OptionDefault(default_expr),
+            ));
+        }
+
+        Self::resolve_into_ffi_conversion(ident, ty)
+    }
+
+    // =====================================================================
+    // Option<T>がNoneの場合の値を#[option_default = value]から決定する関数
+    // (null,null_mut,defaultは対応する値に変換し、それ以外は式として扱う)
+    // =====================================================================
+    fn resolve_option_default(attrs: &[Attribute]) -> syn::Result<TokenStream> {
+        let default_expr = CFFIAttributeAnalyzer::get_expr(attrs, M_ATTR_OPTION_DEFAULT)?;
+        let name = match &default_expr {
+            Some(Expr::Path(expr_path)) => expr_path.path.get_ident().map(Ident::to_string),
+            _ => None,
+        };
+        Ok(match (name.as_deref(), default_expr) {
+            (Some("null"), _) => quote! { ::std::ptr::null() },
+            (Some("null_mut"), _) => quote! { ::std::ptr::null_mut() },
+            (Some("default"), _) | (_, None) => quote! { ::std::default::Default::default() },
+            (_, Some(default_expr)) => quote! { #default_expr },
+        })
+    }
+
+    // =====================================================================
+    // Option<T>以外の変換方法を決定する関数
+    // (IntoFfiArgで変換し、extern宣言側の型は<T as IntoFfiArg>::CTypeとする)
+    // =====================================================================
+    fn resolve_into_ffi_conversion(
+        ident: &Ident,
+        ty: &Type,
+    ) -> syn::Result<(TokenStream, ArgConversion)> {
+        // 関数ポインタ、生ポインタはそのまま
+        if matches!(ty, Type::BareFn(_) | Type::Ptr(_)) {
+            return Ok((quote! { #ty }, ArgConversion::PassThrough));
+        }
+
+        // impl Trait型はトレイトを実装できないため、変換元の式と型を決定する
+        let (source, source_ty, mutable) = match Self::resolve_impl_source(ident, ty)? {
+            Some(resolved) => resolved,
+            None if Self::contains_impl_trait(ty) => {
+                return Err(CFFIErrorKind::UnsupportedImplTrait(
+                    CFFIAnalyzer::tokens_to_string(ty),
+                    "expected `impl AsRef<T>`, `impl AsMut<T>`, `impl Into<T>`, `impl ToString` or `impl Display`",
+                )
+                .spanned(ty));
+            }
+            None => (quote! { #ident }, ty.clone(), false),
+        };
+        let source_ty = Self::erase_lifetimes(source_ty);
+        Ok((
+            quote! { <#source_ty as ::cffi_gen::IntoFfiArg>::CType },
+            ArgConversion::IntoFfi { source, mutable },
+        ))
+    }
+
+    // =====================================================================
+    // impl Trait型の引数をIntoFfiArgの実装された型にする式と、その型を返す関数
+    // (式が引数を&mutで借用する場合はtrueを返す)
+    // =====================================================================
+    fn resolve_impl_source(
+        ident: &Ident,
+        ty: &Type,
+    ) -> syn::Result<Option<(TokenStream, Type, bool)>> {
+        // impl AsRef<T>,&impl AsRef<T>,&mut impl AsRef<T> -> &T
+        if let Some(inner_ty) = CFFIAnalyzer::extract_as_ref_generic(ty) {
+            return Ok(Some((
+                quote! { #ident.as_ref() },
+                syn::parse_quote! { &#inner_ty },
+                false,
+            )));
+        }
+
+        // impl AsMut<T>,&mut impl AsMut<T> -> &mut T
+        if let Some(inner_ty) = CFFIAnalyzer::extract_as_mut_generic(ty) {
+            // &mut strはNUL終端でなく、C側から書き込めないため&mut Stringを使用する
+            if CFFIAnalyzer::is_str(inner_ty) {
+                return Err(CFFIErrorKind::UnsupportedImplTrait(
+                    CFFIAnalyzer::tokens_to_string(ty),
+                    "`AsMut<str>` cannot be passed as a writable C string; use `&mut String`",
+                )
+                .spanned(ty));
+            }
+            let inner_ty: Type = syn::parse_quote! { &mut #inner_ty };
+            return Ok(Some(if CFFIAnalyzer::is_impl(ty) {
+                (
+                    quote! { ::std::convert::AsMut::as_mut(&mut #ident) },
+                    inner_ty,
+                    true,
+                )
+            } else {
+                (quote! { #ident.as_mut() }, inner_ty, false)
+            }));
+        }
+
+        // impl Into<T> -> T
+        if let Some(inner_ty) = CFFIAnalyzer::extract_impl_into_generic(ty) {
+            return Ok(Some((
+                quote! { ::std::convert::Into::<#inner_ty>::into(#ident) },
+                inner_ty.clone(),
+                false,
+            )));
+        }
+
+        // impl ToString,impl Display,&impl ToString,&impl Display -> String
+        let target = match ty {
+            Type::Reference(reference) => &*reference.elem,
+            _ => ty,
+        };
+        if CFFIAnalyzer::is_impl_to_string(target) || CFFIAnalyzer::is_impl_display(target) {
+            return Ok(Some((
+                quote! { #ident.to_string() },
+                syn::parse_quote! { ::std::string::String },
+                false,
+            )));
+        }
+
+        Ok(None)
+    }
+
+    // =====================================================================
+    // 型にimpl Traitが含まれるかを返す関数
+    // =====================================================================
+    fn contains_impl_trait(ty: &Type) -> bool {
+        struct FindImplTrait(bool);
+        impl VisitMut for FindImplTrait {
+            fn visit_type_impl_trait_mut(&mut self, _: &mut TypeImplTrait) {
+                self.0 = true;
+            }
+        }
+        let mut finder = FindImplTrait(false);
+        finder.visit_type_mut(&mut ty.clone());
+        finder.0
+    }
+
+    // =====================================================================
+    // 型のライフタイムを省略形にする関数
+    // (extern宣言では関数のライフタイム引数を参照できないため)
+    // =====================================================================
+    fn erase_lifetimes(mut ty: Type) -> Type {
+        struct EraseLifetimes;
+        impl VisitMut for EraseLifetimes {
+            fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+                if lifetime.ident != "static" {
+                    lifetime.ident = Ident::new("_", lifetime.ident.span());
+                }
+            }
+        }
+        EraseLifetimes.visit_type_mut(&mut ty);
+        ty
+    }
+}
+
+impl CallbackSpec {
+    // =====================================================================
+    // #[callback(userdata = value, userdata_position = N, registered, abi = "C")]、
+    // #[callback(static_slots = N)]とクロージャの型から設定を決定する関数
+    // =====================================================================
+    pub fn build(attr: &Attribute, ty: &Type, default_abi: &LitStr) -> syn::Result<Self> {
+        let mut userdata = None;
+        let mut userdata_position = None;
+        let mut static_slots = None;
+        let mut registered = false;
+        let mut abi = None;
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("userdata") {
+                userdata = Some(meta.value()?.parse::<Ident>()?);
+            } else if meta.path.is_ident("userdata_position") {
+                userdata_position = Some(meta.value()?.parse::<LitInt>()?);
+            } else if meta.path.is_ident("static_slots") {
+                let value = meta.value()?.parse::<LitInt>()?;
+                let slots = value.base10_parse::<usize>()?;
+                if slots == 0 {
+                    return Err(CFFIErrorKind::InvalidCallback(
+                        "`static_slots` must be at least 1".to_string(),
+                    )
+                    .spanned(&value));
+                }
+                static_slots = Some(slots);
+            } else if meta.path.is_ident("registered") {
+                registered = true;
+            } else if meta.path.is_ident("abi") {
+                let value = meta.value()?.parse::<LitStr>()?;
+                CFFIAnalyzer::validate_abi(&value)?;
+                abi = Some(value);
+            } else {
+                return Err(CFFIErrorKind::InvalidCallback(format!(
+                    "unknown option `{}`; expected `userdata`, `userdata_position`, `static_slots`, `registered` or `abi`",
+                    meta.path.to_token_stream()
+                ))
+                .spanned(&meta.path));
+            }
+            Ok(())
+        })?;
+
+        // impl FnMut(A, B) -> R、impl Fn(A, B) -> Rのみ使用可能
+        let signature = match ty {
+            Type::ImplTrait(impl_trait) => impl_trait.bounds.iter().find_map(|bound| match bound {
+                TypeParamBound::Trait(trait_bound) => {
+                    let segment = trait_bound.path.segments.last()?;
+                    match &segment.arguments {
+                        PathArguments::Parenthesized(args)
+                            if segment.ident == "FnMut" || segment.ident == "Fn" =>
+                        {
+                            Some((trait_bound, args))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }),
+            _ => None,
+        };
+        let Some((trait_bound, args)) = signature else {
+            return Err(CFFIErrorKind::InvalidCallback(
+                "callback arguments must be `impl FnMut(...) -> T` or `impl Fn(...) -> T`"
+                    .to_string(),
+            )
+            .spanned(ty));
+        };
+        let inputs = args.inputs.iter().cloned().collect::<Vec<Type>>();
+
+        let context = match (userdata, static_slots) {
+            (Some(arg), None) => {
+                // userdataの位置はクロージャの引数の間(0..=引数の数)のみ指定可能
+                let position = match userdata_position {
+                    Some(lit) => {
+                        let position = lit.base10_parse::<usize>()?;
+                        if position > inputs.len() {
+                            return Err(CFFIErrorKind::InvalidCallback(format!(
+                                "`userdata_position` must be at most {} (the number of closure arguments)",
+                                inputs.len()
+                            ))
+                            .spanned(&lit));
+                        }
+                        position
+                    }
+                    None => inputs.len(),
+                };
+                CallbackContext::Userdata { arg, position }
+            }
+            (None, Some(slots)) => {
+                if let Some(lit) = userdata_position {
+                    return Err(CFFIErrorKind::InvalidCallback(
+                        "`userdata_position` requires `userdata`".to_string(),
+                    )
+                    .spanned(&lit));
+                }
+                CallbackContext::StaticSlots(slots)
+            }
+            (Some(_), Some(_)) => {
+                return Err(CFFIErrorKind::InvalidCallback(
+                    "`userdata` and `static_slots` cannot be used together".to_string(),
+                )
+                .spanned(attr));
+            }
+            (None, None) => {
+                return Err(CFFIErrorKind::InvalidCallback(
+                    "`userdata = <argument>` or `static_slots = N` is required to pass the closure to C"
+                        .to_string(),
+                )
+                .spanned(attr));
+            }
+        };
+
+        Ok(CallbackSpec {
+            bound: quote! { #trait_bound },
+            inputs,
+            output: args.output.clone(),
+            abi: abi.unwrap_or_else(|| default_abi.clone()),
+            context,
+            registered,
+        })
+    }
+
+    // =====================================================================
+    // C側のコールバックの引数の型を返す関数(userdataの場合は指定位置にvoid*を挿入する)
+    // =====================================================================
+    pub fn c_inputs(&self) -> Vec<TokenStream> {
+        let mut inputs = self
+            .inputs
+            .iter()
+            .map(|ty| quote! { #ty })
+            .collect::<Vec<_>>();
+        if let CallbackContext::Userdata { position, .. } = &self.context {
+            inputs.insert(*position, quote! { *mut ::std::ffi::c_void });
+        }
+        inputs
+    }
+
+    // =====================================================================
+    // C側に渡す関数ポインタの型を返す関数
+    // =====================================================================
+    pub fn fn_type(&self) -> TokenStream {
+        let CallbackSpec { output, abi, .. } = self;
+        let inputs = self.c_inputs();
+        quote! { unsafe extern #abi fn(#(#inputs),*) #output }
+    }
+}
+
+impl ReturnSpec {
+    pub fn build(output: &ReturnType) -> Self {
+        // 戻り値の型がない場合は()として扱う
+        let ty = match output {
+            ReturnType::Default => syn::parse_quote! { () },
+            ReturnType::Type(_, ty) => (**ty).clone(),
+        };
+        ReturnSpec {
+            output: output.clone(),
+            ty,
+        }
+    }
+}
+
+impl ErrorPolicy {
+    pub fn build(
+        wrapper_name: &Ident,
+        link_name: &str,
+        args: &[ArgSpec],
+        config_attrs: &[Attribute],
+        attrs: &[Attribute],
+        as_result: bool,
+        ret: &ReturnSpec,
+    ) -> syn::Result<Self> {
+        // 関数単位で指定がある場合、エラー条件を上書き(指定がない場合は戻り値の型から決定)
+        let condition = CFFIAttributeAnalyzer::resolve(
+            attrs,
+            config_attrs,
+            M_ATTR_ERROR_CONDITION,
+            CFFIAttributeAnalyzer::get_expr,
+        )?;
+        let condition = Self::resolve_condition(condition.as_ref(), ret)?;
+
+        // 関数単位で指定がある場合、エラー型とエラー値を上書き
+        let error_type = CFFIAttributeAnalyzer::resolve(
+            attrs,
+            config_attrs,
+            M_ATTR_AS_RESULT_ERROR_TYPE,
+            CFFIAttributeAnalyzer::get_type,
+        )?;
+        let top_priority = CFFIAttributeAnalyzer::resolve(
+            attrs,
+            config_attrs,
+            M_ATTR_AS_RESULT_ERROR_TYPE_TOP_PRIORITY,
+            CFFIAttributeAnalyzer::get_expr,
+        )?;
+
+        // 関数単位で指定がある場合、エラーに引数の値を含める
+        let error_with_args = CFFIAttributeAnalyzer::resolve(
+            attrs,
+            config_attrs,
+            M_ATTR_ERROR_WITH_ARGS,
+            CFFIAttributeAnalyzer::get_bool,
+        )?
+        .unwrap_or(false);
+        let capture_args = error_with_args && as_result && condition.is_some();
+
+        // 引数は変換前の値を保持し、エラー時にのみ文字列にする(impl Trait型はDebugを要求できないため除外)
+        // (Copyと分かる型は値をコピーして保持し、所有権を移動する型のみ変換前に文字列にする)
+        // (そのため、所有権を移動する型はDebugの実装が必要で、エラーの有無に関わらず呼び出しごとに文字列を確保する)
+        let captured_args = args
+            .iter()
+            .filter(|_| capture_args)
+            .filter(|arg| !Self::is_impl_arg(&arg.wrapper_ty))
+            .filter(|arg| !matches!(arg.conversion, ArgConversion::CallbackUserdata))
+            .map(|arg| {
+                let ident = &arg.ident;
+                let name = ident.to_string();
+                let captured = format_ident!("__cffi_arg_{}", ident);
+                if CFFIAnalyzer::is_copy(&arg.wrapper_ty) {
+                    (
+                        quote! { let #captured = #ident; },
+                        quote! { (#name, ::std::format!("{:?}", #captured)) },
+                    )
+                } else {
+                    (
+                        quote! { let #captured = ::std::format!("{:?}", #ident); },
+                        quote! { (#name, #captured) },
+                    )
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let ffi_error = {
+            let wrapper_name = wrapper_name.to_string();
+            let args = if capture_args {
+                let values = captured_args.iter().map(|(_, value)| value);
+                quote! { ::std::option::Option::Some(::std::vec![#(#values),*]) }
+            } else {
+                quote! { ::std::option::Option::None }
+            };
+            quote! {
+                ::cffi_gen::FfiError::Call {
+                    function: #wrapper_name,
+                    symbol: #link_name,
+                    value: ::std::format!("{:?}", result),
+                    args: #args,
+                }
+            }
+        };
+        let is_default = |expr: &Expr| matches!(expr, Expr::Path(p) if p.path.is_ident("default"));
+        // 関数単位のエラー値指定(resultを参照する式も可)
+        let error_variant = CFFIAttributeAnalyzer::get_expr(attrs, M_ATTR_ERROR_VARIANT)?;
+
+        let (error_value, uses_ffi_error) = match (&top_priority, &error_type) {
+            // #[error_variant]は他の指定より優先する
+            _ if error_variant.is_some() => (quote! { #error_variant }, false),
+            // #[as_result_error_type_top_priority = default] はエラー型のデフォルト値を使用
+            (Some(top_priority), Some(error_type)) if is_default(top_priority) => (
+                quote! { <#error_type as ::std::default::Default>::default() },
+                false,
+            ),
+            (Some(top_priority), None) if is_default(top_priority) => (ffi_error, true),
+            (Some(top_priority), _) => (quote! { #top_priority }, false),
+            // エラー型の指定がある場合はFfiErrorから変換する
+            (None, Some(error_type)) => (
+                quote! { <#error_type as ::std::convert::From<::cffi_gen::FfiError>>::from(#ffi_error) },
+                true,
+            ),
+            (None, None) => (ffi_error, true),
+        };
+
+        let args_capture = (capture_args && uses_ffi_error).then(|| {
+            let stmts = captured_args.iter().map(|(stmt, _)| stmt);
+            quote! { #(#stmts)* }
+        });
+
+        // エラー型の指定がない場合はFfiErrorを使用
+        let error_type = match error_type {
+            Some(error_type) => quote! { #error_type },
+            None => quote! { ::cffi_gen::FfiError },
+        };
+
+        Ok(ErrorPolicy {
+            as_result,
+            condition,
+            error_type,
+            error_value,
+            args_capture,
+        })
+    }
+
+    // =====================================================================
+    // エラー条件(プリセットもしくは式)からエラー判定の式を生成する関数
+    // (Noneの場合はエラー判定を行わない)
+    // =====================================================================
+    fn resolve_condition(
+        condition: Option<&Expr>,
+        ret: &ReturnSpec,
+    ) -> syn::Result<Option<TokenStream>> {
+        let Some(condition) = condition else {
+            return Ok(Self::default_condition(ret));
+        };
+        let ty = &ret.ty;
+        let zero = quote! { <#ty as ::std::default::Default>::default() };
+
+        match condition {
+            Expr::Path(expr_path) if expr_path.path.get_ident().is_some() => {
+                let name = expr_path.path.get_ident().map(Ident::to_string);
+                let condition = match name.as_deref() {
+                    Some("default") => Self::default_condition(ret),
+                    Some("never") => None,
+                    Some("negative") => Some(quote! { result < #zero }),
+                    Some("zero") => Some(quote! { result == #zero }),
+                    Some("nonzero") => Some(quote! { result != #zero }),
+                    Some("null") => Some(quote! { result.is_null() }),
+                    Some("eq") | Some("ne") | Some("range") => {
+                        return Err(CFFIErrorKind::InvalidErrorConditionPreset(
+                            Self::preset_usage(name.as_deref()),
+                        )
+                        .spanned(condition));
+                    }
+                    // プリセット以外の識別子は式として扱う
+                    _ => Some(quote! { #condition }),
+                };
+                Ok(condition)
+            }
+            Expr::Call(call) if Self::call_preset_name(call).is_some() => {
+                let name = Self::call_preset_name(call);
+                let usage = Self::preset_usage(name.as_deref());
+                let mut call_args = call.args.iter();
+                let (Some(value), None) = (call_args.next(), call_args.next()) else {
+                    return Err(CFFIErrorKind::InvalidErrorConditionPreset(usage).spanned(call));
+                };
+                let condition = match name.as_deref() {
+                    Some("eq") => quote! { result == #value },
+                    Some("ne") => quote! { result != #value },
+                    _ => {
+                        let Expr::Range(range) = value else {
+                            return Err(
+                                CFFIErrorKind::InvalidErrorConditionPreset(usage).spanned(value)
+                            );
+                        };
+                        quote! { (#range).contains(&result) }
+                    }
+                };
+                Ok(Some(condition))
+            }
+            // プリセット以外はresultを参照する式として扱う
+            _ => Ok(Some(quote! { #condition })),
+        }
+    }
+
+    // =====================================================================
+    // 戻り値の型からデフォルトのエラー条件を決定する関数
+    // (符号付き整数: eq(-1)、ポインタ: null、その他: never)
+    // =====================================================================
+    fn default_condition(ret: &ReturnSpec) -> Option<TokenStream> {
+        if CFFIAnalyzer::is_signed_integer(&ret.ty) {
+            Some(quote! { result == -1 })
+        } else if CFFIAnalyzer::is_pointer(&ret.ty) {
+            Some(quote! { result.is_null() })
+        } else {
+            None
+        }
+    }
+
+    // =====================================================================
+    // 引数を取るプリセット(eq,ne,range)の呼び出しであれば名前を返す関数
+    // =====================================================================
+    fn call_preset_name(call: &ExprCall) -> Option<String> {
+        let Expr::Path(expr_path) = &*call.func else {
+            return None;
+        };
+        let name = expr_path.path.get_ident()?.to_string();
+        matches!(name.as_str(), "eq" | "ne" | "range").then_some(name)
+    }
+
+    // =====================================================================
+    // 引数を取るプリセットの使用例を返す関数
+    // =====================================================================
+    fn preset_usage(name: Option<&str>) -> &'static str {
+        match name {
+            Some("eq") => "eq(value)",
+            Some("ne") => "ne(value)",
+            _ => "range(start..end)",
+        }
+    }
+
+    // =====================================================================
+    // 引数の型がimpl Trait(もしくはその参照)かをboolで返す関数
+    // =====================================================================
+    fn is_impl_arg(ty: &Type) -> bool {
+        CFFIAnalyzer::is_impl(ty)
+            || CFFIAnalyzer::extract_ref(ty)
+                .or_else(|| CFFIAnalyzer::extract_mut(ty))
+                .is_some_and(CFFIAnalyzer::is_impl)
+    }
+}
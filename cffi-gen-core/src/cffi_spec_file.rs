@@ -0,0 +1,278 @@
+use crate::cffi_analyzer::*;
+use crate::cffi_error::*;
+use crate::structs::*;
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::quote;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use syn::parse::Parser;
+use syn::{Attribute, FnArg, Ident, LitStr, Signature, Type};
+
+// 関数の定義でアトリビュート以外として扱うキー
+const FUNCTION_KEYS: &[&str] = &["name", "args", "return", "signature"];
+// 引数の定義でアトリビュート以外として扱うキー
+const ARG_KEYS: &[&str] = &["name", "type"];
+
+// =====================================================================
+// cffi_gen!(spec = value)で指定された宣言ファイル(TOML、JSON)
+// =====================================================================
+pub struct CFFISpecFile {
+    // CARGO_MANIFEST_DIRからの絶対パス(再コンパイル判定用のinclude_bytes!に使用)
+    pub path: PathBuf,
+    // [config]の内容(config{}内のアトリビュートと同じ)
+    pub config_attrs: Vec<Attribute>,
+    // [[functions]]の内容(functions{}内の関数宣言と同じ)
+    pub functions: Vec<FunctionWithAttrs>,
+}
+
+impl CFFISpecFile {
+    // =====================================================================
+    // 宣言ファイルを読み込む関数(拡張子が.jsonの場合はJSON、それ以外はTOMLとして扱う)
+    // (エラーは全て指定されたパスの文字列リテラルを指す)
+    // =====================================================================
+    pub fn load(lit: &LitStr) -> Result<Self, CFFIError> {
+        let name = lit.value();
+        let path = CFFIAnalyzer::manifest_path(&name);
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            CFFIErrorKind::UnreadableFile(path.display().to_string(), e.to_string()).spanned(lit)
+        })?;
+
+        let mut loader = CFFISpecLoader {
+            lit,
+            errors: CFFIError::new(),
+        };
+        let value = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str::<Value>(&content).map_err(|e| e.to_string()),
+            _ => toml::from_str::<Value>(&content).map_err(|e| e.to_string()),
+        }
+        .map_err(|reason| loader.error(reason.trim_end().to_string()))?;
+
+        let (config_attrs, functions) = loader.root(&value);
+        loader.errors.finish(CFFISpecFile {
+            path,
+            config_attrs,
+            functions,
+        })
+    }
+}
+
+// =====================================================================
+// 宣言ファイルの内容をアトリビュートと関数宣言に変換する構造体
+// (最初のエラーで止めず、項目ごとのエラーを全て収集する)
+// =====================================================================
+struct CFFISpecLoader<'a> {
+    // エラーが指す宣言ファイルのパス
+    lit: &'a LitStr,
+    errors: CFFIError,
+}
+
+impl CFFISpecLoader<'_> {
+    // =====================================================================
+    // 宣言ファイルの項目に関するエラーを作成する関数
+    // =====================================================================
+    fn error(&self, reason: String) -> syn::Error {
+        CFFIErrorKind::InvalidSpecFile {
+            file: self.lit.value(),
+            reason,
+        }
+        .spanned(self.lit)
+    }
+
+    fn push_error(&mut self, reason: String) {
+        let error = self.error(reason);
+        self.errors.push(error);
+    }
+
+    // =====================================================================
+    // ファイル全体を変換する関数([config]と[[functions]]のみ使用可能)
+    // =====================================================================
+    fn root(&mut self, value: &Value) -> (Vec<Attribute>, Vec<FunctionWithAttrs>) {
+        let Some(root) = value.as_object() else {
+            self.push_error("expected a table at the top level".to_string());
+            return (Vec::new(), Vec::new());
+        };
+        for key in root.keys() {
+            if !["config", "functions"].contains(&key.as_str()) {
+                self.push_error(format!(
+                    "unknown key `{key}`; expected `config` or `functions`"
+                ));
+            }
+        }
+
+        let config_attrs = match root.get("config") {
+            Some(Value::Object(config)) => match self.attrs(config, &[]) {
+                Ok(attrs) => attrs,
+                Err(e) => {
+                    self.push_error(format!("in `config`: {e}"));
+                    Vec::new()
+                }
+            },
+            Some(_) => {
+                self.push_error("`config` must be a table".to_string());
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+
+        let mut functions = Vec::new();
+        match root.get("functions") {
+            Some(Value::Array(values)) => {
+                for (i, function) in values.iter().enumerate() {
+                    match self.function(function) {
+                        Ok(function) => functions.push(function),
+                        Err(e) => match function.get("name").and_then(Value::as_str) {
+                            Some(name) => {
+                                self.push_error(format!("in `functions[{i}]` (`{name}`): {e}"))
+                            }
+                            None => self.push_error(format!("in `functions[{i}]`: {e}")),
+                        },
+                    }
+                }
+            }
+            Some(_) => self.push_error("`functions` must be an array of tables".to_string()),
+            None => {}
+        }
+        (config_attrs, functions)
+    }
+
+    // =====================================================================
+    // 関数の定義を関数宣言にする関数
+    // (signature = "fn name(...) -> T"、もしくはname,args,returnで指定する)
+    // =====================================================================
+    fn function(&self, value: &Value) -> Result<FunctionWithAttrs, String> {
+        let function = value.as_object().ok_or("expected a table")?;
+        let attrs = self.attrs(function, FUNCTION_KEYS)?;
+
+        if let Some(signature) = function.get("signature") {
+            if let Some(key) = ["name", "args", "return"]
+                .iter()
+                .find(|key| function.contains_key(**key))
+            {
+                return Err(format!("`{key}` cannot be used together with `signature`"));
+            }
+            let signature = signature.as_str().ok_or("`signature` must be a string")?;
+            let sig = self.parse::<Signature>(signature, "signature")?;
+            return Ok(FunctionWithAttrs {
+                attrs,
+                vis: None,
+                sig,
+            });
+        }
+
+        let name = function
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or("`name` (string) or `signature` is required")?;
+        let ident = self.parse::<Ident>(name, "name")?;
+        let args = match function.get("args") {
+            Some(Value::Array(args)) => args
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| self.arg(arg).map_err(|e| format!("in `args[{i}]`: {e}")))
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err("`args` must be an array".to_string()),
+            None => Vec::new(),
+        };
+        let output = match function.get("return") {
+            Some(Value::String(ty)) => {
+                let ty = self.parse::<Type>(ty, "return")?;
+                quote! { -> #ty }
+            }
+            Some(_) => return Err("`return` must be a string".to_string()),
+            None => quote! {},
+        };
+        let sig = syn::parse2::<Signature>(quote! { fn #ident(#(#args),*) #output })
+            .map_err(|e| e.to_string())?;
+        Ok(FunctionWithAttrs {
+            attrs,
+            vis: None,
+            sig,
+        })
+    }
+
+    // =====================================================================
+    // 引数の定義を引数にする関数
+    // ("x: i32"の文字列、もしくはname,typeとアトリビュートのテーブルで指定する)
+    // =====================================================================
+    fn arg(&self, value: &Value) -> Result<TokenStream, String> {
+        match value {
+            Value::String(arg) => {
+                let arg = self.parse::<FnArg>(arg, "argument")?;
+                Ok(quote! { #arg })
+            }
+            Value::Object(arg) => {
+                let attrs = self.attrs(arg, ARG_KEYS)?;
+                let name = arg
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or("`name` (string) is required")?;
+                let ty = arg
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .ok_or("`type` (string) is required")?;
+                let ident = self.parse::<Ident>(name, "name")?;
+                let ty = self.parse::<Type>(ty, "type")?;
+                Ok(quote! { #(#attrs)* #ident: #ty })
+            }
+            _ => Err("expected a string (`x: i32`) or a table".to_string()),
+        }
+    }
+
+    // =====================================================================
+    // テーブルのキーと値をアトリビュートにする関数(指定のキーを除く)
+    // (true -> #[key]、false -> #[key = false]、文字列、数値 -> #[key = value])
+    // =====================================================================
+    fn attrs(&self, table: &Map<String, Value>, skip: &[&str]) -> Result<Vec<Attribute>, String> {
+        table
+            .iter()
+            .filter(|(key, _)| !skip.contains(&key.as_str()))
+            .map(|(key, value)| {
+                let ident = self.parse::<Ident>(key, "attribute name")?;
+                let tokens = match value {
+                    Value::Bool(true) => quote! { #[#ident] },
+                    Value::Bool(false) => quote! { #[#ident = false] },
+                    Value::String(value) => {
+                        let value = LitStr::new(value, self.lit.span());
+                        quote! { #[#ident = #value] }
+                    }
+                    Value::Number(value) => {
+                        let value = self.parse::<syn::Lit>(&value.to_string(), key)?;
+                        quote! { #[#ident = #value] }
+                    }
+                    _ => return Err(format!("`{key}` must be a string, boolean or number")),
+                };
+                Attribute::parse_outer
+                    .parse2(tokens)
+                    .map(|mut attrs| attrs.remove(0))
+                    .map_err(|e| e.to_string())
+            })
+            .collect()
+    }
+
+    // =====================================================================
+    // 文字列を解析する関数(エラー位置を宣言ファイルの指定箇所に合わせる)
+    // =====================================================================
+    fn parse<T: syn::parse::Parse>(&self, text: &str, what: &str) -> Result<T, String> {
+        let tokens = syn::parse_str::<TokenStream>(text)
+            .map_err(|e| format!("invalid {what} `{text}`: {e}"))?;
+        syn::parse2::<T>(Self::respan(tokens, self.lit.span()))
+            .map_err(|e| format!("invalid {what} `{text}`: {e}"))
+    }
+
+    fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+        tokens
+            .into_iter()
+            .map(|mut token| {
+                if let TokenTree::Group(group) = &token {
+                    let mut respanned =
+                        Group::new(group.delimiter(), Self::respan(group.stream(), span));
+                    respanned.set_span(span);
+                    token = TokenTree::Group(respanned);
+                } else {
+                    token.set_span(span);
+                }
+                token
+            })
+            .collect()
+    }
+}
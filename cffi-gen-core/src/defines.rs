@@ -0,0 +1,120 @@
+// =====================================================================
+// =====================================================================
+// マクロアトリビュート名等
+// =====================================================================
+// =====================================================================
+
+// =====================================================================
+// グローバル属性（マクロ全体または関数宣言上部で使用可能）
+// =====================================================================
+pub const M_ATTR_LIBRARY_NAME: &str = "library_name";
+// #[cffi_module(lib = value)],#[cffi(lib = value)]でのlibrary_nameの省略形
+pub const M_ATTR_LIBRARY_NAME_SHORT: &str = "lib";
+pub const M_ATTR_LIBRARY_LINK_TYPE: &str = "link_type";
+// 指定の条件(cfgの述語)を満たす場合のみリンク指定を行う(Windows専用のライブラリ等)
+pub const M_ATTR_LINK_CFG: &str = "link_cfg";
+// #[link_type = "runtime"]の場合のシンボルの解決方法("lazy" or "eager")
+pub const M_ATTR_SYMBOL_RESOLUTION: &str = "symbol_resolution";
+// 宣言した関数がEXPORTSに存在するか検証するモジュール定義ファイル(.def)
+pub const M_ATTR_DEF_FILE: &str = "def_file";
+// .defファイルのEXPORTSのうち、宣言されていないものを定数として出力する
+pub const M_ATTR_DEF_REPORT_UNDECLARED: &str = "def_report_undeclared";
+pub const M_ATTR_ABI: &str = "abi";
+pub const M_ATTR_AS_RESULT: &str = "as_result";
+pub const M_ATTR_AS_RESULT_ERROR_TYPE: &str = "as_result_error_type";
+pub const M_ATTR_AS_RESULT_ERROR_TYPE_TOP_PRIORITY: &str = "as_result_error_type_top_priority";
+pub const M_ATTR_FUNC_NAME_TOP_PREFIX: &str = "func_name_top_prefix";
+pub const M_ATTR_FUNC_NAME_DOWN_PREFIX: &str = "func_name_down_prefix";
+pub const M_ATTR_ERROR_CONDITION: &str = "error_condition";
+pub const M_ATTR_ERROR_WITH_ARGS: &str = "error_with_args";
+pub const M_ATTR_ERROR_VARIANT: &str = "error_variant";
+pub const M_ATTR_ARG_CONVERT: &str = "arg_convert";
+pub const M_ATTR_FUNC_NAME: &str = "func_name";
+pub const M_ATTR_FUNC_ALIAS: &str = "func_alias";
+// #[alias = value]はfunc_aliasの省略形
+pub const M_ATTR_FUNC_ALIAS_SHORT: &str = "alias";
+pub const M_ATTR_NOT_NULL_ASSERT: &str = "not_null_assert";
+// ラップ関数名の命名規則(#[func_alias]が指定された関数には適用しない)
+pub const M_ATTR_RENAME_ALL: &str = "rename_all";
+// ラップ関数名から取り除くプレフィックス(#[rename_all]より先に適用する)
+pub const M_ATTR_STRIP_PREFIX: &str = "strip_prefix";
+// シンボルをリンク時ではなく初回呼び出し時に解決する(存在しない場合はエラーを返す)
+pub const M_ATTR_OPTIONAL: &str = "optional";
+// =====================================================================
+// 関数宣言用属性 (#[cffi_module]内で、ラップ対象の関数を示す)
+// =====================================================================
+pub const M_ATTR_CFFI: &str = "cffi";
+// =====================================================================
+// 引数用属性（関数宣言内の引数に対して使用）
+// =====================================================================
+pub const M_ATTR_AS_ARG_TYPE: &str = "as_arg_type";
+pub const M_ATTR_OPTION_DEFAULT: &str = "option_default";
+pub const M_ATTR_CALLBACK: &str = "callback";
+
+// =====================================================================
+// 汎用属性 (マクロ全体、関数宣言上部、引数のいずれかで使用可能)
+// =====================================================================
+
+// =====================================================================
+// 属性値の定義
+// =====================================================================
+// #[abi = value] で指定可能な呼び出し規約
+pub const SUPPORTED_ABIS: &[&str] = &[
+    "C",
+    "system",
+    "stdcall",
+    "fastcall",
+    "vectorcall",
+    "C-unwind",
+];
+// #[abi]が指定されていない場合の呼び出し規約
+pub const DEFAULT_ABI: &str = "C";
+// 戻り値のデフォルトのエラー条件をeq(-1)とする符号付き整数型
+pub const SIGNED_INTEGER_TYPES: &[&str] = &[
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "c_schar",
+    "c_short",
+    "c_int",
+    "c_long",
+    "c_longlong",
+];
+// Copyを実装しているプリミティブ型(#[error_with_args]で変換前の値をコピーして保持する)
+pub const COPY_PRIMITIVE_TYPES: &[&str] = &[
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "f32",
+    "f64",
+    "bool",
+    "char",
+    "c_char",
+    "c_uchar",
+    "c_ushort",
+    "c_uint",
+    "c_ulong",
+    "c_ulonglong",
+    "c_float",
+    "c_double",
+];
+// 実行時に共有ライブラリを読み込む場合の#[link_type = value]の値
+pub const LINK_TYPE_RUNTIME: &str = "runtime";
+// 検証の対象外とし、関数宣言上部の場合はラップ関数にそのまま付与するアトリビュート
+pub const PASSTHROUGH_ATTRIBUTES: &[&str] = &["doc", "cfg", "cfg_attr", "allow", "deprecated"];
+// ライブラリ構造体に生成されるメソッド、定数の名前(ラップ関数名として使用できない)
+pub const RUNTIME_RESERVED_NAMES: &[&str] = &[
+    "load",
+    "load_from",
+    "resolve_all",
+    "library",
+    "global",
+    "set_global",
+    "LIBRARY_NAME",
+];
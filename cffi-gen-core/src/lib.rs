@@ -0,0 +1,180 @@
+// cffi-gen-core/src/lib.rs
+// cffi_gen!等のマクロとビルドスクリプト用APIで共通して使用する、解析とコード生成の処理
+pub mod cffi_analyzer;
+pub mod cffi_attribute_analyzer;
+pub mod cffi_codegen;
+pub mod cffi_def_file;
+pub mod cffi_error;
+pub mod cffi_expand;
+pub mod cffi_header;
+pub mod cffi_header_codegen;
+pub mod cffi_model;
+pub mod cffi_spec_file;
+pub mod defines;
+pub mod structs;
+
+use structs::*;
+use syn::{
+    Attribute, Ident, LitStr, Signature, Token, braced,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+};
+
+impl Parse for FunctionWithAttrs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = match input.peek(Token![pub]) {
+            true => Some(input.parse()?),
+            false => None,
+        };
+        let sig: Signature = input.parse()?;
+        Ok(FunctionWithAttrs { attrs, vis, sig })
+    }
+}
+
+impl Parse for CFFIGenInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `spec = "path"` (宣言ファイルから読み込む場合)
+        if input.peek(Ident) && input.peek2(Token![=]) {
+            let ident: Ident = input.parse()?;
+            if ident != "spec" {
+                return Err(syn::Error::new(ident.span(), "expected `config` or `spec`"));
+            }
+            input.parse::<Token![=]>()?;
+            let spec: LitStr = input.parse()?;
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+            return Ok(CFFIGenInput {
+                config_attrs: Vec::new(),
+                fns: Punctuated::new(),
+                spec: Some(spec),
+            });
+        }
+
+        // `config` ブロック
+        input.parse::<Ident>().and_then(|ident| {
+            if ident != "config" {
+                return Err(syn::Error::new(ident.span(), "expected `config`"));
+            }
+            Ok(())
+        })?;
+
+        let content_config;
+        braced!(content_config in input);
+        //let config_attrs = content_config.call(syn::Attribute::parse_outer)?;
+
+        // ←ここで自前ループ
+        let mut config_attrs = Vec::new();
+        while !content_config.is_empty() {
+            // Attribute::parse_outer は Vec<Attribute> を返す
+            let mut attrs = content_config.call(syn::Attribute::parse_outer)?;
+            config_attrs.append(&mut attrs);
+
+            // カンマがあれば消費して次へ
+            if content_config.peek(Token![,]) {
+                content_config.parse::<Token![,]>()?;
+            }
+        }
+        // `functions` ブロック
+        input.parse::<Ident>().and_then(|ident| {
+            if ident != "functions" {
+                return Err(syn::Error::new(ident.span(), "expected `functions`"));
+            }
+            Ok(())
+        })?;
+
+        let content_functions;
+        braced!(content_functions in input);
+
+        let fns = Punctuated::<FunctionWithAttrs, Token![,]>::parse_terminated(&content_functions)?;
+        Ok(CFFIGenInput {
+            config_attrs,
+            fns,
+            spec: None,
+        })
+    }
+}
+impl Parse for FunctionOverride {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let ident: Ident = input.parse()?;
+        Ok(FunctionOverride { attrs, ident })
+    }
+}
+
+impl Parse for CFFIHeaderInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `header = "path"`
+        input.parse::<Ident>().and_then(|ident| {
+            if ident != "header" {
+                return Err(syn::Error::new(ident.span(), "expected `header`"));
+            }
+            Ok(())
+        })?;
+        input.parse::<Token![=]>()?;
+        let header: LitStr = input.parse()?;
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+
+        let mut result = CFFIHeaderInput {
+            header,
+            config_attrs: Vec::new(),
+            calling_conventions: Vec::new(),
+            ignore: Vec::new(),
+            allow: Vec::new(),
+            block: Vec::new(),
+            overrides: Vec::new(),
+        };
+        // 各ブロックは省略可能、順不同
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            let content;
+            braced!(content in input);
+            match ident.to_string().as_str() {
+                "config" => {
+                    while !content.is_empty() {
+                        let mut attrs = content.call(Attribute::parse_outer)?;
+                        result.config_attrs.append(&mut attrs);
+                        if content.peek(Token![,]) {
+                            content.parse::<Token![,]>()?;
+                        }
+                    }
+                }
+                "calling_conventions" => {
+                    while !content.is_empty() {
+                        let name: LitStr = content.parse()?;
+                        content.parse::<Token![=]>()?;
+                        let abi: LitStr = content.parse()?;
+                        result.calling_conventions.push((name, abi));
+                        if content.peek(Token![,]) {
+                            content.parse::<Token![,]>()?;
+                        }
+                    }
+                }
+                "ignore" | "allow" | "block" => {
+                    let names = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    let target = match ident.to_string().as_str() {
+                        "ignore" => &mut result.ignore,
+                        "allow" => &mut result.allow,
+                        _ => &mut result.block,
+                    };
+                    target.extend(names);
+                }
+                "overrides" => {
+                    let overrides =
+                        Punctuated::<FunctionOverride, Token![,]>::parse_terminated(&content)?;
+                    result.overrides.extend(overrides);
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "expected one of `config`, `calling_conventions`, `ignore`, `allow`, `block`, `overrides`",
+                    ));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
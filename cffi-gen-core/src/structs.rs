@@ -0,0 +1,40 @@
+use syn::Attribute;
+use syn::Ident;
+use syn::LitStr;
+use syn::Signature;
+use syn::Token;
+use syn::Visibility;
+use syn::punctuated::Punctuated;
+// 属性付き関数
+pub struct FunctionWithAttrs {
+    pub attrs: Vec<Attribute>,
+    // ラップ関数の可視性(指定がない場合はpub、#[cffi_module]、#[cffi]では記述通り)
+    pub vis: Option<Visibility>,
+    pub sig: Signature,
+}
+// マクロ全体
+pub struct CFFIGenInput {
+    pub config_attrs: Vec<Attribute>,
+    pub fns: Punctuated<FunctionWithAttrs, Token![,]>,
+    // spec = "path"で宣言ファイルを指定された場合(config_attrs,fnsは空)
+    pub spec: Option<LitStr>,
+}
+// ヘッダーの関数に追加するアトリビュート
+pub struct FunctionOverride {
+    pub attrs: Vec<Attribute>,
+    pub ident: Ident,
+}
+// cffi_gen_from_header!全体
+pub struct CFFIHeaderInput {
+    pub header: LitStr,
+    pub config_attrs: Vec<Attribute>,
+    // 呼び出し規約として扱うマクロ名とabi
+    pub calling_conventions: Vec<(LitStr, LitStr)>,
+    // 無視するマクロ名
+    pub ignore: Vec<LitStr>,
+    // 対象とする名前のパターン(正規表現)
+    pub allow: Vec<LitStr>,
+    // 除外する名前のパターン(正規表現)
+    pub block: Vec<LitStr>,
+    pub overrides: Vec<FunctionOverride>,
+}
@@ -0,0 +1,15 @@
+extern crate proc_macro;
+use cffi_gen_core::cffi_expand::expand_derive_into_ffi_arg;
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+// =====================================================================
+// #[derive(IntoFfiArg)]での実装処理をする関数
+// =====================================================================
+pub fn generate_derive_into_ffi_arg(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_derive_into_ffi_arg(&input) {
+        Ok(output) => TokenStream::from(output),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
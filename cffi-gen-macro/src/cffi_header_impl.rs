@@ -0,0 +1,16 @@
+extern crate proc_macro;
+use cffi_gen_core::cffi_expand::expand_cffi_gen_from_header;
+use cffi_gen_core::structs::*;
+use proc_macro::TokenStream;
+use syn::parse_macro_input;
+
+// =====================================================================
+// cffi_gen_from_headerマクロでの実装処理をする関数
+// =====================================================================
+pub fn generate_cffi_gen_from_header(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as CFFIHeaderInput);
+    match expand_cffi_gen_from_header(input) {
+        Ok(output) => TokenStream::from(output),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
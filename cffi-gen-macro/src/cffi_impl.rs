@@ -1,21 +1,46 @@
 extern crate proc_macro;
-use crate::cffi_analyzer::*;
-use anyhow::Result;
+use cffi_gen_core::cffi_analyzer::*;
+use cffi_gen_core::cffi_error::*;
+use cffi_gen_core::cffi_expand::expand_cffi_gen;
+use cffi_gen_core::structs::*;
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
-use std::sync::Mutex;
 use syn::{
-    Expr, ExprLit, FnArg, GenericArgument, Ident, Lit, LitStr, Meta, MetaNameValue, Pat, PatType,
-    PathArguments, ReturnType, Signature, Token, Type, TypeParamBound, TypePath, TypeReference,
-    braced,
-    parse::{Parse, ParseStream},
-    parse_macro_input, parse_str,
+    Meta, Token, Visibility,
+    parse::{ParseStream, Parser},
+    parse_macro_input,
     punctuated::Punctuated,
 };
 
 // =====================================================================
 // cffiアトリビュートでの実装処理をする関数
+// (#[cffi_module]内の#[cffi]はモジュール側で処理されるため、
+// ここではモジュール外で単独使用された場合のみを扱う)
 // =====================================================================
-pub fn generate_cffi(_attr: TokenStream, _item: TokenStream) -> TokenStream {
-    quote! {}.into()
+pub fn generate_cffi(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let metas = parse_macro_input!(attr with Punctuated::<Meta, Token![,]>::parse_terminated);
+
+    // 本体なしの関数宣言(`fn name() -> i32;`)
+    let parser = |input: ParseStream| {
+        let function: FunctionWithAttrs = input.parse()?;
+        if input.peek(syn::token::Brace) {
+            return Err(CFFIErrorKind::FunctionWithBody.at(input.span()));
+        }
+        input.parse::<Token![;]>()?;
+        Ok(function)
+    };
+    let mut function = match parser.parse(item) {
+        Ok(function) => function,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    // 可視性の指定がない場合は通常の関数と同じく非公開とする
+    function.vis.get_or_insert(Visibility::Inherited);
+
+    // 単独使用時は#[cffi(...)]の引数がconfig{}に相当する
+    let config_attrs = CFFIAnalyzer::metas_to_attrs(metas);
+
+    match expand_cffi_gen(&config_attrs, [&function]) {
+        Ok(output) => TokenStream::from(output),
+        Err(e) => e.to_compile_error().into(),
+    }
 }
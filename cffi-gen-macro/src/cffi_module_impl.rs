@@ -1,21 +1,112 @@
 extern crate proc_macro;
-use crate::cffi_analyzer::*;
-use anyhow::Result;
+use cffi_gen_core::cffi_analyzer::*;
+use cffi_gen_core::cffi_error::*;
+use cffi_gen_core::cffi_expand::expand_cffi_gen;
+use cffi_gen_core::defines::*;
+use cffi_gen_core::structs::*;
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
-use std::sync::Mutex;
+use quote::quote;
 use syn::{
-    Expr, ExprLit, FnArg, GenericArgument, Ident, Lit, LitStr, Meta, MetaNameValue, Pat, PatType,
-    PathArguments, ReturnType, Signature, Token, Type, TypeParamBound, TypePath, TypeReference,
-    braced,
-    parse::{Parse, ParseStream},
-    parse_macro_input, parse_str,
+    Item, ItemMod, Meta, Token, Visibility,
+    parse::{ParseStream, Parser},
+    parse_macro_input,
     punctuated::Punctuated,
 };
 
 // =====================================================================
 // cffi_moduleアトリビュートでの実装処理をする関数
 // =====================================================================
-pub fn generate_cffi_module(_attr: TokenStream, _item: TokenStream) -> TokenStream {
-    quote! {}.into()
+pub fn generate_cffi_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let metas = parse_macro_input!(attr with Punctuated::<Meta, Token![,]>::parse_terminated);
+    let ItemMod {
+        attrs,
+        vis,
+        unsafety,
+        mod_token,
+        ident,
+        content,
+        semi,
+    } = parse_macro_input!(item as ItemMod);
+
+    // `mod name;` の形式は中身が見えないため未対応
+    let Some((_, items)) = content else {
+        return CFFIErrorKind::ModuleWithoutBody
+            .spanned(quote! { #mod_token #ident #semi })
+            .to_compile_error()
+            .into();
+    };
+
+    // モジュールの引数がconfig{}に相当する
+    let config_attrs = CFFIAnalyzer::metas_to_attrs(metas);
+
+    let mut errors = quote! {};
+    let mut other_items = Vec::new();
+    let mut fns = Vec::new();
+    for item in items {
+        match parse_cffi_item(item) {
+            Ok(CFFIModuleItem::Function(function)) => fns.push(function),
+            Ok(CFFIModuleItem::Other(item)) => other_items.push(item),
+            Err(e) => errors.extend(e.to_compile_error()),
+        }
+    }
+
+    let generated = expand_cffi_gen(&config_attrs, &fns).unwrap_or_else(|e| e.to_compile_error());
+
+    TokenStream::from(quote! {
+        #(#attrs)*
+        #vis #unsafety #mod_token #ident {
+            #(#other_items)*
+            #generated
+            #errors
+        }
+    })
+}
+
+// モジュール内のアイテムの分類
+enum CFFIModuleItem {
+    // #[cffi]付きの関数宣言
+    Function(FunctionWithAttrs),
+    // それ以外(そのまま出力する)
+    Other(Item),
+}
+
+// =====================================================================
+// モジュール内のアイテムが#[cffi]付きの関数宣言であれば取り出す関数
+// =====================================================================
+fn parse_cffi_item(item: Item) -> syn::Result<CFFIModuleItem> {
+    match item {
+        // 本体なしの関数宣言(`fn name() -> i32;`)はsynではVerbatimとして扱われる
+        Item::Verbatim(tokens) => {
+            let parser = |input: ParseStream| {
+                let function: FunctionWithAttrs = input.parse()?;
+                input.parse::<Token![;]>()?;
+                Ok(function)
+            };
+            let Ok(FunctionWithAttrs {
+                mut attrs,
+                vis,
+                sig,
+            }) = parser.parse2(tokens.clone())
+            else {
+                return Ok(CFFIModuleItem::Other(Item::Verbatim(tokens)));
+            };
+            match CFFIAnalyzer::take_cffi_attr(&mut attrs)? {
+                // #[cffi(...)]の引数は関数宣言上部のアトリビュートと同じ扱い
+                Some(mut cffi_attrs) => {
+                    cffi_attrs.append(&mut attrs);
+                    // 可視性の指定がない場合は通常の関数と同じくモジュール内のみとする
+                    Ok(CFFIModuleItem::Function(FunctionWithAttrs {
+                        attrs: cffi_attrs,
+                        vis: Some(vis.unwrap_or(Visibility::Inherited)),
+                        sig,
+                    }))
+                }
+                None => Ok(CFFIModuleItem::Other(Item::Verbatim(tokens))),
+            }
+        }
+        Item::Fn(item_fn) if CFFIAnalyzer::has_path_attr(&item_fn.attrs, M_ATTR_CFFI) => {
+            Err(CFFIErrorKind::FunctionWithBody.spanned(&item_fn.block))
+        }
+        item => Ok(CFFIModuleItem::Other(item)),
+    }
 }
@@ -0,0 +1,313 @@
+use cffi_gen_core::cffi_analyzer::CFFIAnalyzer;
+use cffi_gen_core::cffi_error::CFFIError;
+use cffi_gen_core::cffi_expand::{expand_cffi_gen, expand_cffi_gen_from_header};
+use cffi_gen_core::cffi_header::{CFFIHeaderParser, CHeaderOptions};
+use cffi_gen_core::cffi_header_codegen::CFFIHeaderCodegen;
+use cffi_gen_core::cffi_spec_file::CFFISpecFile;
+use cffi_gen_core::structs::{CFFIGenInput, CFFIHeaderInput, FunctionWithAttrs};
+use std::path::Path;
+use syn::parse::Parser;
+use syn::{Attribute, Expr, ExprMacro, Item, ItemConst, ItemMod, LitStr};
+use thiserror::Error;
+
+// 生成したファイルの先頭に付けるコメント
+const GENERATED_HEADER: &str = "// @generated by cffi-gen. Do not edit by hand.\n\n";
+
+// =====================================================================
+// ビルドスクリプト用APIのエラー型
+// =====================================================================
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum BuildError {
+    // config、関数宣言の文字列をRustのコードとして解析できなかった
+    #[error("failed to parse `{source_text}`: {reason}")]
+    Parse {
+        // 指定された文字列
+        source_text: String,
+        // 解析に失敗した理由
+        reason: String,
+    },
+    // cffi_gen!と同じ検証でエラーになった(未知のアトリビュート等)
+    #[error(transparent)]
+    Generate(#[from] CFFIError),
+    // 生成したコードをRustのファイルとして整形できなかった
+    #[error("failed to format generated code: {0}")]
+    Format(String),
+    // 入力ファイルを読み込めなかった、出力先に書き込めなかった
+    #[error("failed to access `{path}`: {error}")]
+    Io { path: String, error: std::io::Error },
+}
+
+impl BuildError {
+    // =====================================================================
+    // 解析エラーを作成する関数(複数行の場合は先頭行のみをメッセージに含める)
+    // =====================================================================
+    fn parse(text: &str, error: syn::Error) -> Self {
+        let mut lines = text.trim().lines();
+        let first = lines.next().unwrap_or_default();
+        BuildError::Parse {
+            source_text: match lines.next() {
+                Some(_) => format!("{first} ..."),
+                None => first.to_string(),
+            },
+            reason: error.to_string(),
+        }
+    }
+}
+
+// =====================================================================
+// cffi_gen!と同じコード生成を行い、整形済みのRustのソースを出力するビルダー
+// (build.rsからOUT_DIRに書き出し、include!で読み込む)
+//
+// let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+// cffi_gen::build::Builder::new()
+//     .library("DxLib_x64")
+//     .config("#[as_result]")
+//     .function("fn DxLib_Init() -> i32")
+//     .write_to(out_dir.join("dxlib.rs"))?;
+// =====================================================================
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    // config{}内のアトリビュート(`#[as_result]`等の文字列)
+    config: Vec<String>,
+    // functions{}内の関数宣言(`#[alias = "init"] fn DxLib_Init() -> i32`等の文字列)
+    functions: Vec<String>,
+    // cffi_gen!の本体(`config{...} functions{...}`、`spec = "path"`の文字列)
+    specs: Vec<String>,
+    // 生成元のCヘッダー(cffi_gen_from_header!のheader = valueと同じ扱い)
+    header: Option<String>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // =====================================================================
+    // #[library_name = value]を指定する関数
+    // =====================================================================
+    pub fn library(self, name: &str) -> Self {
+        self.config(&format!("#[library_name = {name:?}]"))
+    }
+
+    // =====================================================================
+    // config{}内のアトリビュートを追加する関数(`#[a] #[b]`のように複数指定も可能)
+    // =====================================================================
+    pub fn config(mut self, attrs: &str) -> Self {
+        self.config.push(attrs.to_string());
+        self
+    }
+
+    // =====================================================================
+    // functions{}内の関数宣言を追加する関数(末尾の`,`、`;`は省略可能)
+    // =====================================================================
+    pub fn function(mut self, declaration: &str) -> Self {
+        self.functions.push(declaration.to_string());
+        self
+    }
+
+    // =====================================================================
+    // cffi_gen!の本体を追加する関数(`cffi_gen! { ... }`全体を指定することも可能)
+    // =====================================================================
+    pub fn spec(mut self, body: &str) -> Self {
+        self.specs.push(body.to_string());
+        self
+    }
+
+    // =====================================================================
+    // Cヘッダーの関数、型、定数を生成対象にする関数
+    // (相対パスはCARGO_MANIFEST_DIRからのパス、設定されていない場合は現在のディレクトリから)
+    // =====================================================================
+    pub fn header(mut self, path: impl AsRef<Path>) -> Self {
+        self.header = Some(path.as_ref().display().to_string());
+        self
+    }
+
+    // =====================================================================
+    // config{}内のアトリビュートと全ての関数宣言を返す関数(生成内容の確認用)
+    // (ヘッダーの関数はcffi_gen_from_header!と同じ方法で関数宣言にしたもの)
+    // =====================================================================
+    pub fn declarations(&self) -> Result<(Vec<Attribute>, Vec<FunctionWithAttrs>), BuildError> {
+        let (config_attrs, mut functions) = self.parse_inputs()?;
+        if let Some(header) = &self.header {
+            let path = CFFIAnalyzer::manifest_path(header);
+            let source = std::fs::read_to_string(&path).map_err(|error| BuildError::Io {
+                path: path.display().to_string(),
+                error,
+            })?;
+            let options = CHeaderOptions::default();
+            let mut errors = CFFIError::new();
+            let header_functions = CFFIHeaderParser::parse(&source, &options)
+                .functions
+                .iter()
+                .filter_map(|function| {
+                    errors.collect(CFFIHeaderCodegen::function(
+                        function,
+                        Vec::new(),
+                        &config_attrs,
+                    ))
+                })
+                .collect::<Vec<_>>();
+            errors.finish(())?;
+            functions.splice(0..0, header_functions);
+        }
+        Ok((config_attrs, functions))
+    }
+
+    // =====================================================================
+    // 整形済みのRustのソースを生成する関数
+    // (宣言ファイル、ヘッダー、.defファイルの再コンパイル判定用のinclude_bytes!は含めない)
+    // =====================================================================
+    pub fn generate(&self) -> Result<String, BuildError> {
+        self.generate_with_dependencies().map(|(source, _)| source)
+    }
+
+    // =====================================================================
+    // 生成したソースを指定パスに書き出す関数
+    // (内容が変わらない場合は書き込まず、不要な再コンパイルを避ける)
+    // (入力ファイルはcargo:rerun-if-changedとして出力し、変更時にbuild.rsを再実行させる)
+    // =====================================================================
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<(), BuildError> {
+        let path = path.as_ref();
+        let (source, dependencies) = self.generate_with_dependencies()?;
+        for dependency in &dependencies {
+            println!("cargo:rerun-if-changed={dependency}");
+        }
+        if std::fs::read_to_string(path).is_ok_and(|current| current == source) {
+            return Ok(());
+        }
+        std::fs::write(path, source).map_err(|error| BuildError::Io {
+            path: path.display().to_string(),
+            error,
+        })
+    }
+
+    // =====================================================================
+    // 整形済みのRustのソースと、生成に使用した入力ファイルのパスを返す関数
+    // =====================================================================
+    fn generate_with_dependencies(&self) -> Result<(String, Vec<String>), BuildError> {
+        let (config_attrs, functions) = self.parse_inputs()?;
+
+        let mut tokens = match &self.header {
+            Some(header) => expand_cffi_gen_from_header(CFFIHeaderInput {
+                header: Self::parse_with(
+                    &format!("{header:?}"),
+                    <LitStr as syn::parse::Parse>::parse,
+                )?,
+                config_attrs: config_attrs.clone(),
+                calling_conventions: Vec::new(),
+                ignore: Vec::new(),
+                allow: Vec::new(),
+                block: Vec::new(),
+                overrides: Vec::new(),
+            })?,
+            None => Default::default(),
+        };
+        if self.header.is_none() || !functions.is_empty() {
+            tokens.extend(expand_cffi_gen(&config_attrs, &functions)?);
+        }
+        let mut file =
+            syn::parse2::<syn::File>(tokens).map_err(|e| BuildError::Format(e.to_string()))?;
+
+        // 絶対パスを生成ファイルに残さないため、include_bytes!の定数を取り除く
+        let mut dependencies = self
+            .specs
+            .iter()
+            .filter_map(|body| Self::parse_spec(body).ok()?.spec)
+            .map(|spec| {
+                CFFIAnalyzer::manifest_path(&spec.value())
+                    .display()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        Self::take_included_files(&mut file.items, &mut dependencies);
+        dependencies.sort();
+        dependencies.dedup();
+        Ok((
+            format!("{GENERATED_HEADER}{}", prettyplease::unparse(&file)),
+            dependencies,
+        ))
+    }
+
+    // =====================================================================
+    // `const _: &[u8] = include_bytes!(path);`を取り除き、パスを追加する関数
+    // (インラインのモジュール内も対象とする)
+    // =====================================================================
+    fn take_included_files(items: &mut Vec<Item>, dependencies: &mut Vec<String>) {
+        items.retain_mut(|item| match item {
+            Item::Const(ItemConst { ident, expr, .. }) if ident == "_" => {
+                let Expr::Macro(ExprMacro { mac, .. }) = expr.as_ref() else {
+                    return true;
+                };
+                if !mac.path.is_ident("include_bytes") {
+                    return true;
+                }
+                match mac.parse_body::<LitStr>() {
+                    Ok(path) => {
+                        dependencies.push(path.value());
+                        false
+                    }
+                    Err(_) => true,
+                }
+            }
+            Item::Mod(ItemMod {
+                content: Some((_, items)),
+                ..
+            }) => {
+                Self::take_included_files(items, dependencies);
+                true
+            }
+            _ => true,
+        });
+    }
+
+    // =====================================================================
+    // config()、function()、spec()で指定された文字列を解析する関数
+    // =====================================================================
+    fn parse_inputs(&self) -> Result<(Vec<Attribute>, Vec<FunctionWithAttrs>), BuildError> {
+        let mut config_attrs = Vec::new();
+        let mut functions = Vec::new();
+        for body in &self.specs {
+            let CFFIGenInput {
+                config_attrs: attrs,
+                fns,
+                spec,
+            } = Self::parse_spec(body)?;
+            config_attrs.extend(attrs);
+            functions.extend(fns);
+            // spec = "path"の場合は宣言ファイルから読み込む
+            if let Some(spec) = spec {
+                let spec_file = CFFISpecFile::load(&spec)?;
+                config_attrs.extend(spec_file.config_attrs);
+                functions.extend(spec_file.functions);
+            }
+        }
+        for attrs in &self.config {
+            config_attrs.extend(Self::parse_with(attrs, Attribute::parse_outer)?);
+        }
+        for declaration in &self.functions {
+            let declaration = declaration.trim().trim_end_matches([',', ';']);
+            functions.push(Self::parse_with(declaration, syn::parse::Parse::parse)?);
+        }
+        Ok((config_attrs, functions))
+    }
+
+    // =====================================================================
+    // cffi_gen!の本体を解析する関数(`cffi_gen! { ... }`の場合は中身を解析する)
+    // =====================================================================
+    fn parse_spec(body: &str) -> Result<CFFIGenInput, BuildError> {
+        match syn::parse_str::<syn::Macro>(body) {
+            Ok(mac) => mac.parse_body().map_err(|e| BuildError::parse(body, e)),
+            Err(_) => Self::parse_with(body, syn::parse::Parse::parse),
+        }
+    }
+
+    // =====================================================================
+    // 文字列を指定のパーサーで解析する関数
+    // =====================================================================
+    fn parse_with<T>(text: &str, parser: impl Parser<Output = T>) -> Result<T, BuildError> {
+        parser
+            .parse_str(text)
+            .map_err(|e| BuildError::parse(text, e))
+    }
+}
@@ -0,0 +1,347 @@
+use std::any::Any;
+use std::ffi::c_void;
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// パニック時のペイロード
+pub type PanicPayload = Box<dyn Any + Send + 'static>;
+
+thread_local! {
+    // スレッドを識別するための変数(アドレスをスレッドのIDとして使用する)
+    static THREAD_MARKER: u8 = const { 0 };
+}
+
+// =====================================================================
+// #[callback]のクロージャと、コールバック内で発生したパニックを保持する構造体
+// (void*のuserdataとしてC側に渡し、トランポリンから呼び出す)
+// =====================================================================
+pub struct CallbackState<F> {
+    // 複数のスレッドから呼ばれた場合は、ロックで順番に呼び出す
+    closure: Mutex<F>,
+    // クロージャを実行中のスレッド(0の場合は実行中でない、再入の検出に使用)
+    owner: AtomicUsize,
+    // パニックはC側に伝播させず、最初のペイロードを保持する
+    panic: Mutex<Option<PanicPayload>>,
+}
+
+impl<F> CallbackState<F> {
+    pub fn new(closure: F) -> Self {
+        Self {
+            closure: Mutex::new(closure),
+            owner: AtomicUsize::new(0),
+            panic: Mutex::new(None),
+        }
+    }
+
+    // =====================================================================
+    // C側に渡すuserdataのポインタを返す関数
+    // =====================================================================
+    pub fn as_userdata(&self) -> *mut c_void {
+        self as *const Self as *mut c_void
+    }
+
+    // =====================================================================
+    // トランポリンからクロージャを呼び出す関数
+    // =====================================================================
+    /// # Safety
+    /// userdataはas_userdata()で取得した、有効なCallbackState<F>を指すこと
+    pub unsafe fn call<R: CallbackReturn>(userdata: *mut c_void, f: impl FnOnce(&mut F) -> R) -> R {
+        let state = unsafe { &*(userdata as *const Self) };
+        state.invoke(f)
+    }
+
+    // =====================================================================
+    // クロージャを呼び出す関数
+    // (パニックした場合はペイロードを保持し、CallbackReturn::panic_value()を返す)
+    // (実行中のクロージャから同じスレッドで再入した場合は呼び出さず、panic_value()を返す)
+    // =====================================================================
+    pub fn invoke<R: CallbackReturn>(&self, f: impl FnOnce(&mut F) -> R) -> R {
+        let thread = THREAD_MARKER.with(|marker| marker as *const u8 as usize);
+        if self.owner.load(Ordering::Acquire) == thread {
+            return R::panic_value();
+        }
+        let mut closure = self.closure.lock().unwrap_or_else(|e| e.into_inner());
+        self.owner.store(thread, Ordering::Release);
+        let result = catch_unwind(AssertUnwindSafe(|| f(&mut closure)));
+        self.owner.store(0, Ordering::Release);
+        drop(closure);
+        match result {
+            Ok(value) => value,
+            Err(payload) => {
+                let mut panic = self.panic.lock().unwrap_or_else(|e| e.into_inner());
+                panic.get_or_insert(payload);
+                R::panic_value()
+            }
+        }
+    }
+
+    // =====================================================================
+    // コールバック内で発生したパニックを取り出す関数
+    // =====================================================================
+    pub fn take_panic(&self) -> Option<PanicPayload> {
+        self.panic.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    // =====================================================================
+    // 呼び出し中のコールバックでパニックが発生していた場合、呼び出し元でパニックを再開する関数
+    // =====================================================================
+    pub fn resume_panic(&self) {
+        if let Some(payload) = self.take_panic() {
+            resume_unwind(payload);
+        }
+    }
+}
+
+// =====================================================================
+// 登録したコールバックの状態を型を消して保持するためのトレイト
+// =====================================================================
+trait RegisteredCallback: Send {
+    fn take_panic(&self) -> Option<PanicPayload>;
+}
+
+impl<F: Send> RegisteredCallback for CallbackState<F> {
+    fn take_panic(&self) -> Option<PanicPayload> {
+        CallbackState::take_panic(self)
+    }
+}
+
+// =====================================================================
+// #[callback(registered)]で登録したクロージャの所有権を持つハンドル
+// (破棄するとクロージャを解放するため、C側で登録を解除するまで保持すること)
+// =====================================================================
+#[must_use = "dropping the registration frees the callback while C may still call it"]
+#[derive(Default)]
+pub struct CallbackRegistration {
+    states: Vec<Box<dyn RegisteredCallback>>,
+}
+
+impl std::fmt::Debug for CallbackRegistration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackRegistration")
+            .field("callbacks", &self.states.len())
+            .finish()
+    }
+}
+
+impl CallbackRegistration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // =====================================================================
+    // 登録したクロージャの状態を追加する関数(生成コードから使用)
+    // =====================================================================
+    pub fn push<F: Send + 'static>(&mut self, state: Box<CallbackState<F>>) {
+        self.states.push(state);
+    }
+
+    // =====================================================================
+    // 登録したstatic_slotsのスロットを追加する関数(生成コードから使用)
+    // =====================================================================
+    pub fn push_slot(&mut self, slot: CallbackSlot) {
+        self.states.push(Box::new(slot));
+    }
+
+    // =====================================================================
+    // 登録したコールバック内で発生したパニックを取り出す関数
+    // =====================================================================
+    pub fn take_panic(&self) -> Option<PanicPayload> {
+        self.states.iter().find_map(|state| state.take_panic())
+    }
+
+    // =====================================================================
+    // クロージャを解放せず、プログラムの終了まで有効にする関数
+    // (登録を解除する手段がないAPIで使用する)
+    // =====================================================================
+    pub fn leak(self) {
+        std::mem::forget(self);
+    }
+}
+
+// =====================================================================
+// #[callback(static_slots = N)]のクロージャを保持するテーブル
+// (userdataを受け取れないコールバック用に、スロットごとに生成したトランポリンから呼び出す)
+// =====================================================================
+pub struct StaticSlots<F: ?Sized + 'static, const N: usize> {
+    // ロックはスロットの参照、更新の間のみ保持し、クロージャの呼び出し中は保持しない
+    slots: [Mutex<Option<SlotState<F>>>; N],
+}
+
+// スロットに登録したクロージャと、コールバック内で発生したパニック
+// (呼び出し中に解放された場合も、呼び出しが終わるまでクロージャを保持する)
+type SlotState<F> = Arc<CallbackState<Box<F>>>;
+
+impl<F: ?Sized + Send + 'static, const N: usize> StaticSlots<F, N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { Mutex::new(None) }; N],
+        }
+    }
+
+    // =====================================================================
+    // 空いているスロットにクロージャを登録する関数(全て使用中の場合はNone)
+    // =====================================================================
+    pub fn register(&'static self, closure: Box<F>) -> Option<CallbackSlot> {
+        for (index, slot) in self.slots.iter().enumerate() {
+            let mut slot = slot.lock().unwrap_or_else(|e| e.into_inner());
+            if slot.is_none() {
+                *slot = Some(Arc::new(CallbackState::new(closure)));
+                return Some(CallbackSlot { table: self, index });
+            }
+        }
+        None
+    }
+
+    // =====================================================================
+    // トランポリンからスロットのクロージャを呼び出す関数
+    // (スロットが解放済み、実行中のクロージャへの再入、パニックした場合は
+    // CallbackReturn::panic_value()を返す)
+    // =====================================================================
+    pub fn call<R: CallbackReturn>(&self, index: usize, f: impl FnOnce(&mut F) -> R) -> R {
+        match self.state(index) {
+            Some(state) => state.invoke(|closure| f(&mut **closure)),
+            None => R::panic_value(),
+        }
+    }
+
+    // =====================================================================
+    // スロットの状態を取り出す関数(ロックはすぐに解放する)
+    // =====================================================================
+    fn state(&self, index: usize) -> Option<SlotState<F>> {
+        self.slots[index]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl<F: ?Sized + Send + 'static, const N: usize> Default for StaticSlots<F, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =====================================================================
+// スロットの解放、パニックの取り出しをクロージャの型を消して行うためのトレイト
+// =====================================================================
+trait SlotTable: Sync {
+    fn release(&self, index: usize);
+    fn take_panic(&self, index: usize) -> Option<PanicPayload>;
+}
+
+impl<F: ?Sized + Send + 'static, const N: usize> SlotTable for StaticSlots<F, N> {
+    fn release(&self, index: usize) {
+        // クロージャの破棄はロックの外で行う
+        let state = self.slots[index]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        drop(state);
+    }
+
+    fn take_panic(&self, index: usize) -> Option<PanicPayload> {
+        self.state(index).and_then(|state| state.take_panic())
+    }
+}
+
+// =====================================================================
+// StaticSlotsに登録したクロージャのスロットを所有するハンドル
+// (破棄するとスロットを解放し、別のクロージャを登録できるようにする)
+// =====================================================================
+#[must_use = "dropping the slot frees the callback while C may still call it"]
+pub struct CallbackSlot {
+    table: &'static dyn SlotTable,
+    index: usize,
+}
+
+impl CallbackSlot {
+    // =====================================================================
+    // スロットの番号(使用するトランポリンの番号)を返す関数
+    // =====================================================================
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    // =====================================================================
+    // コールバック内で発生したパニックを取り出す関数
+    // =====================================================================
+    pub fn take_panic(&self) -> Option<PanicPayload> {
+        self.table.take_panic(self.index)
+    }
+
+    // =====================================================================
+    // 呼び出し中のコールバックでパニックが発生していた場合、呼び出し元でパニックを再開する関数
+    // =====================================================================
+    pub fn resume_panic(&self) {
+        if let Some(payload) = self.take_panic() {
+            resume_unwind(payload);
+        }
+    }
+}
+
+impl std::fmt::Debug for CallbackSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallbackSlot")
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl Drop for CallbackSlot {
+    fn drop(&mut self) {
+        self.table.release(self.index);
+    }
+}
+
+impl RegisteredCallback for CallbackSlot {
+    fn take_panic(&self) -> Option<PanicPayload> {
+        CallbackSlot::take_panic(self)
+    }
+}
+
+// =====================================================================
+// コールバックがパニックした場合にC側へ返す値
+// (static_slotsのスロットが解放済みの場合にも返す)
+// =====================================================================
+pub trait CallbackReturn {
+    fn panic_value() -> Self;
+}
+
+macro_rules! impl_callback_return {
+    ($($ty:ty => $value:expr),* $(,)?) => {
+        $(
+            impl CallbackReturn for $ty {
+                fn panic_value() -> Self {
+                    $value
+                }
+            }
+        )*
+    };
+}
+
+impl_callback_return!(
+    () => (),
+    bool => false,
+    i8 => 0, i16 => 0, i32 => 0, i64 => 0, isize => 0,
+    u8 => 0, u16 => 0, u32 => 0, u64 => 0, usize => 0,
+    f32 => 0.0, f64 => 0.0,
+);
+
+impl<T> CallbackReturn for *const T {
+    fn panic_value() -> Self {
+        std::ptr::null()
+    }
+}
+
+impl<T> CallbackReturn for *mut T {
+    fn panic_value() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl<T> CallbackReturn for Option<T> {
+    fn panic_value() -> Self {
+        None
+    }
+}
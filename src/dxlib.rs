@@ -1,5 +1,4 @@
 #![allow(non_snake_case)]
-use crate::utils::*;
 use cffi_gen_macro::cffi_gen;
 use thiserror::Error;
 
@@ -10,6 +9,8 @@ pub enum DxLibError {
     #[error("Failed to DxLib_End()")]
     FinalizeError,
     #[error(transparent)]
+    Ffi(#[from] crate::FfiError),
+    #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
@@ -29,16 +30,21 @@ cffi_gen! {
     config{
         #[library_name = "DxLib_x64"], // ライブラリ名
         #[link_type = "dylib"], // リンクタイプ
-        #[as_result], // 関数戻り値をanyhow::Resultに変換
+        #[link_cfg = "windows"], // DxLibはWindows専用のため、Windowsでのみリンクする
+        #[abi = "system"], // 呼び出し規約(x86ではstdcall、それ以外ではC)
+        #[as_result], // 関数戻り値をResult<_, DxLibError>に変換(FfiErrorはFromでDxLibError::Ffiに変換)
+        #[as_result_error_type = DxLibError], // 関数戻り値のエラー型をDxLibErrorにする
         #[arg_convert = default], // 関数引数の変換処理をデフォルトにする
         #[func_name_top_prefix = "dx_" ], // ffi関数生成関数の最初にdx_をつけて生成
     }
     functions{
         // ライブラリの初期化
         //#[alias = "dxlib_init"]
-        //#[as_result] // 関数戻り値をanyhow::Resultに変換
+        //#[as_result] // 関数単位でResultに変換する場合
+        #[error_variant = DxLibError::InitializeError] // エラー時の値
         fn DxLib_Init() -> i32,
         // ライブラリ使用の終了関数
+        #[error_variant = DxLibError::FinalizeError]
         fn DxLib_End() -> i32,
         fn ChangeWindowMode(mode: i32) -> i32,
         fn TestFunc(p: impl AsRef<str>) -> i32,
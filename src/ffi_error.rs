@@ -0,0 +1,111 @@
+use thiserror::Error;
+
+// =====================================================================
+// 生成されたラップ関数が返すデフォルトのエラー型
+// (#[as_result_error_type]が指定されていない場合に使用される)
+// 独自のエラー型を使用する場合は、`#[from] FfiError`を持つバリアントを用意すること
+// =====================================================================
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FfiError {
+    // エラー条件に一致する戻り値が返された
+    #[error("{function}() failed: `{symbol}` returned {value}{}", FfiError::format_args(.args))]
+    Call {
+        // ラップ関数名
+        function: &'static str,
+        // リンク時のシンボル名
+        symbol: &'static str,
+        // 戻り値を文字列にしたもの
+        value: String,
+        // 引数名と値を文字列にしたもの(#[error_with_args]指定時のみ)
+        args: Option<Vec<(&'static str, String)>>,
+    },
+    // 共有ライブラリを読み込めなかった(#[link_type = "runtime"]指定時)
+    #[error("failed to load library `{library}`: {reason}")]
+    LibraryUnavailable {
+        // ライブラリのパス
+        library: String,
+        // 読み込みに失敗した理由
+        reason: String,
+    },
+    // 共有ライブラリに指定シンボルが存在しない
+    #[error("symbol `{symbol}` is not available in `{library}`")]
+    SymbolUnavailable {
+        // ライブラリのパス
+        library: String,
+        // シンボル名
+        symbol: &'static str,
+    },
+    // 引数をC側の型に変換できなかった(#[as_arg_type = value]指定時)
+    #[error("{function}() failed: argument `{arg}` cannot be converted to `{ty}`: {reason}")]
+    InvalidArgument {
+        // ラップ関数名
+        function: &'static str,
+        // 引数名
+        arg: &'static str,
+        // 変換先の型
+        ty: &'static str,
+        // 変換に失敗した理由
+        reason: String,
+    },
+    // IntoFfiArg::try_into_ffi()で値をC側の値に変換できなかった
+    // (ラップ関数ではInvalidArgumentのreasonとして返す)
+    #[error("{0}")]
+    Conversion(String),
+    // #[callback(static_slots = N)]のスロットが全て使用中
+    #[error("{function}() failed: all {slots} static callback slots for `{arg}` are in use")]
+    CallbackSlotsExhausted {
+        // ラップ関数名
+        function: &'static str,
+        // 引数名
+        arg: &'static str,
+        // スロットの数
+        slots: usize,
+    },
+}
+
+impl FfiError {
+    // =====================================================================
+    // エラーとなったラップ関数名を返す関数(関数呼び出し以外のエラーはNone)
+    // =====================================================================
+    pub fn function(&self) -> Option<&'static str> {
+        match self {
+            FfiError::Call { function, .. }
+            | FfiError::InvalidArgument { function, .. }
+            | FfiError::CallbackSlotsExhausted { function, .. } => Some(function),
+            _ => None,
+        }
+    }
+
+    // =====================================================================
+    // エラーとなったシンボル名を返す関数(ライブラリの読み込みエラーはNone)
+    // =====================================================================
+    pub fn symbol(&self) -> Option<&'static str> {
+        match self {
+            FfiError::Call { symbol, .. } | FfiError::SymbolUnavailable { symbol, .. } => {
+                Some(symbol)
+            }
+            FfiError::LibraryUnavailable { .. }
+            | FfiError::InvalidArgument { .. }
+            | FfiError::Conversion(_)
+            | FfiError::CallbackSlotsExhausted { .. } => None,
+        }
+    }
+
+    // =====================================================================
+    // 引数をエラーメッセージ用に整形する関数
+    // =====================================================================
+    fn format_args(args: &Option<Vec<(&'static str, String)>>) -> String {
+        match args {
+            Some(args) => {
+                let args = args
+                    .iter()
+                    .map(|(name, value)| format!("{name} = {value}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(" (args: {args})")
+            }
+            None => String::new(),
+        }
+    }
+}
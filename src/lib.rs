@@ -1,18 +1,1084 @@
+// 生成コードは::cffi_gen::...でパスを参照するため、クレート内でも同じパスで参照できるようにする
+extern crate self as cffi_gen;
+
+pub mod build;
+pub mod callback;
+// DxLibはWindows専用のため、Windowsでのみリンクする(他の環境では型検査のみ行う)
 pub mod dxlib;
+pub mod ffi_error;
+pub mod runtime;
 pub mod utils;
 
+pub use ffi_error::FfiError;
+pub use utils::{IntoFfiArg, ReprC};
+
+#[cfg(test)]
 mod tests {
     use crate::dxlib::*;
     use anyhow::Result as R;
-    use std::f64::consts::PI;
-    use std::ffi::CStr;
-    use std::os::raw::c_char;
 
-    #[test]
-    fn test_dxlib_1() -> R<(), DxLibError> {
+    // DxLibの初期化と終了
+    // (DxLibはWindowsでのみ実行し、他の環境ではリンクせずに型検査のみ行う)
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn dxlib_1() -> R<(), DxLibError> {
         ChangeWindowMode(1)?;
         DxLib_Init()?;
         DxLib_End()?;
         Ok(())
     }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_dxlib_1() -> R<(), DxLibError> {
+        dxlib_1()
+    }
+
+    // libc(cdecl)のラップ
+    #[cfg(unix)]
+    mod libc {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[abi = "C"],
+                #[arg_convert = false],
+            }
+            functions{
+                #[alias = "c_abs"]
+                fn abs(x: i32) -> i32,
+                // "l" + "ab" + "s" -> labs(関数単位の指定は後続の関数に影響しない)
+                #[func_name_top_prefix = "l"]
+                #[func_name_down_prefix = "s"]
+                #[alias = "c_labs_affixed"]
+                fn ab(x: i64) -> i64,
+                // 同じシンボルを別名の関数として宣言する
+                #[func_name = "abs"]
+                fn absolute(x: i32) -> i32,
+                #[optional]
+                #[alias = "c_labs"]
+                fn labs(x: i64) -> i64,
+                #[optional]
+                fn cffi_gen_missing_symbol() -> i32,
+            }
+        }
+    }
+
+    // ライブラリ名の指定がない#[optional]の関数(実行中のプロセスから探す)
+    #[cfg(unix)]
+    #[allow(non_snake_case)]
+    mod local_optional {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{}
+            functions{
+                #[optional]
+                fn cffi_gen_missing_local() -> i32,
+                // 大文字小文字のみ異なる関数もシンボルの保持先が衝突しない
+                #[optional]
+                fn cffi_gen_missing_Local() -> i32,
+            }
+        }
+    }
+
+    // libcのラップ(エラー時にFfiErrorを返す)
+    #[cfg(unix)]
+    mod libc_result {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[as_result],
+                #[error_with_args],
+            }
+            functions{
+                #[alias = "c_close"]
+                fn close(fd: i32) -> i32,
+                #[func_name = "abs"]
+                #[alias = "c_abs_checked"]
+                fn abs_checked(#[as_arg_type = i32] x: i64) -> i32,
+                #[func_name = "abs"]
+                #[alias = "c_abs_widened"]
+                fn abs_widened(#[as_arg_type = i32] x: i16) -> i32,
+                #[func_name = "abs"]
+                #[alias = "c_abs_lossy"]
+                fn abs_lossy(#[as_arg_type(lossy = i32)] x: i64) -> i32,
+                #[alias = "c_chdir"]
+                #[arg_convert]
+                fn chdir(path: String) -> i32,
+            }
+        }
+    }
+
+    // libcのラップ(独自のエラー型を使用する)
+    #[cfg(unix)]
+    mod libc_error_type {
+        use cffi_gen_macro::cffi_gen;
+
+        #[derive(Debug, Default, thiserror::Error)]
+        pub enum LibcError {
+            #[default]
+            #[error("unknown error")]
+            Unknown,
+            #[error("already closed")]
+            Closed,
+            #[error("returned {0}")]
+            Code(i32),
+            #[error(transparent)]
+            Ffi(#[from] crate::FfiError),
+        }
+
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[as_result],
+                #[as_result_error_type = LibcError],
+            }
+            functions{
+                // エラー値はFfiErrorから変換する
+                #[func_name = "close"]
+                fn close_from(fd: i32) -> i32,
+                #[func_name = "close"]
+                #[as_result_error_type_top_priority = LibcError::Closed]
+                fn close_top_priority(fd: i32) -> i32,
+                #[func_name = "close"]
+                #[as_result_error_type_top_priority = default]
+                fn close_default(fd: i32) -> i32,
+                // #[error_variant]はtop_priorityより優先し、resultを参照できる
+                #[func_name = "close"]
+                #[as_result_error_type_top_priority = LibcError::Closed]
+                #[error_variant = LibcError::Code(result)]
+                fn close_variant(fd: i32) -> i32,
+            }
+        }
+    }
+
+    // libcのラップ(エラー条件のプリセットを使用する)
+    #[cfg(unix)]
+    mod libc_condition {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[as_result],
+            }
+            functions{
+                #[func_name = "close"]
+                #[error_condition = negative]
+                fn close_negative(fd: i32) -> i32,
+                #[func_name = "abs"]
+                #[error_condition = eq(3)]
+                fn abs_eq(x: i32) -> i32,
+                #[func_name = "abs"]
+                #[error_condition = range(0..10)]
+                fn abs_range(x: i32) -> i32,
+                #[func_name = "abs"]
+                #[error_condition = nonzero]
+                fn abs_nonzero(x: i32) -> i32,
+            }
+        }
+    }
+
+    // libcのラップ(ラップ関数名を変換する)
+    #[cfg(unix)]
+    mod libc_rename {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[strip_prefix = "C_"],
+                #[rename_all = "snake_case"],
+            }
+            functions{
+                #[func_name = "abs"]
+                fn C_AbsoluteValue(x: i32) -> i32,
+                #[func_name = "labs"]
+                fn LongABSValue(x: i64) -> i64,
+                #[func_name = "abs"]
+                #[alias = "c_abs"]
+                fn AbsAlias(x: i32) -> i32,
+            }
+        }
+    }
+
+    // #[cffi_module]によるlibcのラップ(#[cffi]のないアイテムはそのまま残る)
+    #[cfg(unix)]
+    #[cffi_gen_macro::cffi_module(lib = "c", as_result)]
+    mod libc_module {
+        pub const NEGATIVE: i64 = -7;
+
+        #[cffi(as_result = false)]
+        pub fn abs(x: i32) -> i32;
+        #[cffi(alias = "c_close")]
+        pub(crate) fn close(fd: i32) -> i32;
+        // 可視性の指定がない場合はモジュール内のみで使用できる
+        #[cffi(func_name = "labs", as_result = false)]
+        fn labs_private(x: i64) -> i64;
+
+        pub fn labs_twice(x: i64) -> i64 {
+            labs_private(x) * 2
+        }
+    }
+
+    // #[cffi]の単独使用
+    #[cfg(unix)]
+    mod libc_single {
+        #[cffi_gen_macro::cffi(lib = "c")]
+        pub fn labs(x: i64) -> i64;
+    }
+
+    // DxLibの.defファイルによるシンボルの検証(DxLibが存在しない環境のため#[optional]で宣言する)
+    #[cfg(unix)]
+    mod dxlib_def {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "DxLib_x64"],
+                #[def_file = "DxLib_x64.def"],
+                #[def_report_undeclared],
+                #[func_name_top_prefix = "dx_"],
+                #[rename_all = "snake_case"],
+            }
+            functions{
+                #[optional]
+                fn DxLib_Init() -> i32,
+                #[optional]
+                fn DxLib_End() -> i32,
+            }
+        }
+    }
+
+    // libcのラップ(実行時に読み込む)
+    #[cfg(target_os = "linux")]
+    mod libc_runtime {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[link_type = "runtime"],
+                #[as_result],
+            }
+            functions{
+                #[alias = "c_close"]
+                fn close(fd: i32) -> i32,
+                #[as_result = false]
+                fn abs(x: i32) -> i32,
+                fn cffi_gen_missing_symbol() -> i32,
+            }
+        }
+    }
+
+    // #[doc],#[cfg],#[deprecated]等はラップ関数にそのまま付与する
+    // (#[cfg]で除外した関数は、読み込み時のシンボルの解決対象からも除外される)
+    #[cfg(target_os = "linux")]
+    mod libc_passthrough {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[link_type = "runtime"],
+                #[symbol_resolution = "eager"],
+            }
+            functions{
+                /// 絶対値を返す
+                #[cfg(unix)]
+                fn abs(x: i32) -> i32,
+                #[cfg(any())]
+                fn cffi_gen_missing_symbol() -> i32,
+                #[deprecated = "use abs"]
+                fn labs(x: i64) -> i64,
+            }
+        }
+    }
+
+    // IntoFfiArgを実装した独自の型を引数に使用する
+    #[cfg(unix)]
+    mod libc_convert {
+        use cffi_gen_macro::cffi_gen;
+
+        pub struct Negated(pub i64);
+        impl crate::IntoFfiArg for Negated {
+            type CType = i64;
+            type Guard = ();
+            fn into_ffi(self) -> (Self::CType, Self::Guard) {
+                (-self.0, ())
+            }
+        }
+
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[arg_convert],
+            }
+            functions{
+                #[func_name = "labs"]
+                #[alias = "c_labs_negated"]
+                fn labs_negated(x: Negated) -> i64,
+                #[alias = "c_memset"]
+                fn memset(s: &mut impl AsMut<[u8]>, c: i32, n: usize) -> *mut u8,
+            }
+        }
+    }
+
+    // テスト内で定義したC ABIの関数に、impl Trait、NULを含む文字列、独自の参照の変換、Option<&str>、&CStrを渡す
+    mod local_convert {
+        use cffi_gen_macro::cffi_gen;
+        use std::ffi::{CStr, c_char};
+
+        pub struct Magnitude(pub i64);
+        impl crate::IntoFfiArg for &Magnitude {
+            type CType = i64;
+            type Guard = ();
+            fn into_ffi(self) -> (Self::CType, Self::Guard) {
+                (self.0.abs(), ())
+            }
+        }
+
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_identity(x: i64) -> i64 {
+            x
+        }
+
+        // NULLの場合は-1、それ以外は文字列の長さを返す
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_strlen_or_null(s: *const c_char) -> i64 {
+            match s.is_null() {
+                true => -1,
+                false => unsafe { CStr::from_ptr(s) }.count_bytes() as i64,
+            }
+        }
+
+        // バイト列の合計を返す
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_sum_bytes(bytes: *const u8, len: usize) -> i64 {
+            let bytes = unsafe { std::slice::from_raw_parts(bytes, len) };
+            bytes.iter().map(|&b| b as i64).sum()
+        }
+
+        cffi_gen! {
+            config{
+                #[abi = "C"],
+                #[arg_convert],
+            }
+            functions{
+                #[func_name = "cffi_gen_test_sum_bytes"]
+                fn sum_bytes(bytes: impl Into<Vec<u8>>, len: usize) -> i64,
+                #[func_name = "cffi_gen_test_strlen_or_null"]
+                fn strlen_display(s: &impl std::fmt::Display) -> i64,
+                #[func_name = "cffi_gen_test_strlen_or_null"]
+                #[as_result]
+                fn strlen_checked(s: &str) -> i64,
+                #[func_name = "cffi_gen_test_identity"]
+                fn magnitude(x: &Magnitude) -> i64,
+                #[func_name = "cffi_gen_test_strlen_or_null"]
+                fn strlen_or_null(s: Option<&str>) -> i64,
+                #[func_name = "cffi_gen_test_strlen_or_null"]
+                fn strlen_c_str(s: &CStr) -> i64,
+            }
+        }
+    }
+
+    // 装飾済みのシンボル名(`?Foo@@YAHXZ`等)を#[func_name]で指定する
+    #[cfg(unix)]
+    mod local_decorated {
+        use cffi_gen_macro::cffi_gen;
+
+        #[unsafe(export_name = "?cffi_gen_test_decorated")]
+        extern "C" fn decorated(x: i64) -> i64 {
+            x + 1
+        }
+
+        cffi_gen! {
+            config{}
+            functions{
+                #[func_name = "?cffi_gen_test_decorated"]
+                fn decorated_increment(x: i64) -> i64,
+            }
+        }
+    }
+
+    // #[repr(C)]の構造体の値渡し、参照渡し(Noneの場合はnull)
+    #[cfg(target_os = "linux")]
+    mod libc_struct {
+        use cffi_gen_macro::{IntoFfiArg, cffi_gen};
+
+        #[repr(C)]
+        #[derive(Default, IntoFfiArg)]
+        pub struct TimeVal {
+            pub tv_sec: i64,
+            pub tv_usec: i64,
+        }
+        #[repr(C)]
+        #[derive(IntoFfiArg)]
+        pub struct TimeZone {
+            pub tz_minuteswest: i32,
+            pub tz_dsttime: i32,
+        }
+        #[repr(transparent)]
+        #[derive(IntoFfiArg)]
+        pub struct Value(pub i32);
+
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+                #[arg_convert],
+            }
+            functions{
+                #[alias = "c_gettimeofday"]
+                fn gettimeofday(tv: &mut TimeVal, tz: Option<&mut TimeZone>) -> i32,
+                #[alias = "c_time"]
+                fn time(t: Option<&mut i64>) -> i64,
+                #[func_name = "abs"]
+                #[alias = "c_abs_value"]
+                fn abs_value(x: Value) -> i32,
+            }
+        }
+    }
+
+    // クロージャをuserdata経由でコールバックとして渡す
+    #[cfg(target_os = "linux")]
+    mod libc_callback {
+        use cffi_gen_macro::cffi_gen;
+        use std::ffi::c_void;
+
+        cffi_gen! {
+            config{
+                #[library_name = "c"],
+            }
+            functions{
+                #[alias = "c_qsort_r"]
+                fn qsort_r(
+                    base: *mut c_void,
+                    nmemb: usize,
+                    size: usize,
+                    #[callback(userdata = arg)] compar: impl FnMut(*const c_void, *const c_void) -> i32,
+                    arg: *mut c_void,
+                ),
+                #[alias = "c_on_exit"]
+                fn on_exit(
+                    #[callback(userdata = arg, registered)] function: impl FnMut(i32),
+                    arg: *mut c_void,
+                ) -> i32,
+                #[alias = "c_qsort"]
+                fn qsort(
+                    base: *mut c_void,
+                    nmemb: usize,
+                    size: usize,
+                    #[callback(static_slots = 1)] compar: impl FnMut(*const c_void, *const c_void) -> i32,
+                ),
+                #[alias = "c_atexit"]
+                #[as_result]
+                fn atexit(#[callback(static_slots = 1, registered)] function: impl FnMut()) -> i32,
+            }
+        }
+    }
+
+    // テスト内で定義したC ABIの関数にコールバックを渡す
+    mod local_callback {
+        use cffi_gen_macro::cffi_gen;
+        use std::cell::Cell;
+        use std::ffi::c_void;
+
+        type Callback = unsafe extern "C" fn(*mut c_void, i32) -> i32;
+
+        thread_local! {
+            // 呼び出し中のコールバック(再入のテストに使用)
+            static CURRENT: Cell<Option<(Callback, *mut c_void)>> = const { Cell::new(None) };
+        }
+
+        // userdataを最初の引数で受け取るコールバックを呼び出す
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_apply(
+            callback: Callback,
+            userdata: *mut c_void,
+            x: i32,
+        ) -> i32 {
+            CURRENT.set(Some((callback, userdata)));
+            let result = unsafe { callback(userdata, x) };
+            CURRENT.set(None);
+            result
+        }
+
+        // 呼び出し中のコールバックを再度呼び出す
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_reenter(x: i32) -> i32 {
+            let (callback, userdata) = CURRENT.get().unwrap();
+            unsafe { callback(userdata, x) }
+        }
+
+        type Hook = unsafe extern "C" fn(i32) -> i32;
+
+        thread_local! {
+            // 呼び出し中のuserdataのないコールバック
+            static CURRENT_HOOK: Cell<Option<Hook>> = const { Cell::new(None) };
+        }
+
+        // userdataのないコールバックを呼び出す
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_apply_hook(hook: Hook, x: i32) -> i32 {
+            CURRENT_HOOK.set(Some(hook));
+            let result = unsafe { hook(x) };
+            CURRENT_HOOK.set(None);
+            result
+        }
+
+        // 呼び出し中のuserdataのないコールバックを再度呼び出す
+        #[unsafe(no_mangle)]
+        extern "C" fn cffi_gen_test_reenter_hook(x: i32) -> i32 {
+            unsafe { CURRENT_HOOK.get().unwrap()(x) }
+        }
+
+        cffi_gen! {
+            config{
+                #[abi = "C"],
+            }
+            functions{
+                #[alias = "apply_hook"]
+                fn cffi_gen_test_apply_hook(
+                    #[callback(static_slots = 2)] hook: impl FnMut(i32) -> i32,
+                    x: i32,
+                ) -> i32,
+                #[alias = "reenter_hook"]
+                fn cffi_gen_test_reenter_hook(x: i32) -> i32,
+                #[alias = "apply"]
+                fn cffi_gen_test_apply(
+                    #[callback(userdata = userdata, userdata_position = 0)] callback: impl FnMut(i32) -> i32,
+                    userdata: *mut c_void,
+                    x: i32,
+                ) -> i32,
+                #[alias = "reenter"]
+                fn cffi_gen_test_reenter(x: i32) -> i32,
+            }
+        }
+    }
+
+    // 宣言ファイルからの生成
+    #[cfg(unix)]
+    mod libc_spec {
+        use cffi_gen_macro::cffi_gen;
+        cffi_gen!(spec = "tests/libc_spec.toml");
+    }
+
+    // Cヘッダーからの生成(libcに存在しない関数は除外する)
+    #[cfg(unix)]
+    mod libc_header {
+        use cffi_gen_macro::cffi_gen_from_header;
+        cffi_gen_from_header! {
+            header = "tests/libc_subset.h",
+            config {
+                #[library_name = "c"],
+                #[arg_convert],
+            }
+            block { "cffi_.*" }
+            overrides {
+                #[alias = "c_abs"]
+                abs,
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_libc_abi() {
+        assert_eq!(libc::c_abs(-3), 3);
+        assert_eq!(libc::absolute(-4), 4);
+        assert_eq!(libc::c_labs_affixed(-5), 5);
+        assert_eq!(libc_rename::absolute_value(-6), 6);
+        assert_eq!(libc_rename::long_abs_value(-7), 7);
+        assert_eq!(libc_rename::c_abs(-8), 8);
+        assert_eq!(libc_spec::c_abs(-9), 9);
+        assert_eq!(libc_spec::strlen("spec"), 4);
+        assert_eq!(
+            libc_spec::c_close(-1).unwrap_err().to_string(),
+            "c_close() failed: `close` returned -1 (args: fd = -1)"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cffi_module() {
+        assert_eq!(libc_module::abs(-3), 3);
+        assert_eq!(libc_module::labs_twice(libc_module::NEGATIVE), 14);
+        assert!(matches!(
+            libc_module::c_close(-1),
+            Err(crate::FfiError::Call { .. })
+        ));
+        assert_eq!(libc_single::labs(-4), 4);
+
+        // 宣言の可視性をラップ関数に引き継ぐ(指定がない場合はpub)
+        use crate::build::Builder;
+        let source = Builder::new()
+            .function("pub(crate) fn abs(x: i32) -> i32")
+            .function("fn labs(x: i64) -> i64")
+            .generate()
+            .unwrap();
+        assert!(source.contains("pub(crate) fn abs(x: i32) -> i32 {"));
+        assert!(source.contains("pub fn labs(x: i64) -> i64 {"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_optional_symbol() {
+        assert!(libc::c_labs_is_available());
+        assert_eq!(libc::c_labs(-3).unwrap(), 3);
+        assert!(!libc::cffi_gen_missing_symbol_is_available());
+        // libc.soを読み込めずに実行中のプロセスで代用した場合は、元の読み込みエラーを返す
+        assert!(matches!(
+            libc::cffi_gen_missing_symbol(),
+            Err(crate::FfiError::LibraryUnavailable { .. })
+        ));
+        // ライブラリ名の指定がない場合は実行中のプロセスから探す
+        assert!(matches!(
+            local_optional::cffi_gen_missing_local(),
+            Err(crate::FfiError::SymbolUnavailable { .. })
+        ));
+        assert!(matches!(
+            local_optional::cffi_gen_missing_Local(),
+            Err(crate::FfiError::SymbolUnavailable { .. })
+        ));
+
+        // {ラップ関数名}_is_availableと同名のラップ関数は#[alias]での変更を求める
+        use crate::build::Builder;
+        let builder = Builder::new()
+            .config(r#"#[library_name = "c"]"#)
+            .function("#[optional] fn foo() -> i32");
+        let error = builder
+            .clone()
+            .function("fn foo_is_available() -> i32")
+            .generate()
+            .unwrap_err();
+        assert!(error.to_string().contains(
+            "`foo_is_available` conflicts with the availability check generated for #[optional] `foo`"
+        ));
+        assert!(
+            builder
+                .function(r#"#[alias = "c_foo_is_available"] fn foo_is_available() -> i32"#)
+                .generate()
+                .is_ok()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ffi_error() {
+        let error = libc_result::c_close(-1).unwrap_err();
+        assert_eq!(error.function(), Some("c_close"));
+        assert_eq!(error.symbol(), Some("close"));
+        assert_eq!(
+            error.to_string(),
+            "c_close() failed: `close` returned -1 (args: fd = -1)"
+        );
+
+        // 引数はエラー時にのみ文字列にする(所有権を移動する型のみ変換前に文字列にする)
+        let source = crate::build::Builder::new()
+            .config("#[as_result] #[error_with_args] #[arg_convert]")
+            .function("fn open(path: String, flags: i32) -> i32")
+            .generate()
+            .unwrap();
+        assert!(source.contains("let __cffi_arg_flags = flags;"));
+        assert!(source.contains("let __cffi_arg_path = ::std::format!(\"{:?}\", path);"));
+        assert!(!source.contains("::std::format!(\"{:?}\", flags)"));
+        // 所有権を移動する型(Debugの実装が必要)は、呼び出しごとに文字列にしてエラーに含める
+        assert_eq!(
+            libc_result::c_chdir("/cffi_gen_missing_dir".to_string())
+                .unwrap_err()
+                .to_string(),
+            "c_chdir() failed: `chdir` returned -1 (args: path = \"/cffi_gen_missing_dir\")"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_error_type() {
+        use libc_error_type::*;
+        assert!(matches!(
+            close_from(-1),
+            Err(LibcError::Ffi(crate::FfiError::Call { .. }))
+        ));
+        assert!(matches!(close_top_priority(-1), Err(LibcError::Closed)));
+        assert!(matches!(close_default(-1), Err(LibcError::Unknown)));
+        assert!(matches!(close_variant(-1), Err(LibcError::Code(-1))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_error_condition() {
+        use libc_condition::*;
+        assert!(close_negative(-1).is_err());
+        assert!(abs_eq(-3).is_err() && abs_eq(-4).unwrap() == 4);
+        assert!(abs_range(-3).is_err() && abs_range(-10).unwrap() == 10);
+        assert!(abs_nonzero(-3).is_err() && abs_nonzero(0).unwrap() == 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_decorated_symbol() {
+        assert_eq!(local_decorated::decorated_increment(1), 2);
+
+        // 装飾済みの名前は\x01を付けて、リンカ側での再装飾(`_`の付加等)を抑止する
+        let source = crate::build::Builder::new()
+            .config("#[abi = \"stdcall\"]")
+            .function(r#"#[func_name = "_Foo@8"] fn foo(a: i32, b: i32) -> i32"#)
+            .generate()
+            .unwrap();
+        assert!(source.contains(r#"#[link_name = "\u{1}_Foo@8"]"#));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_as_arg_type() {
+        assert_eq!(libc_result::c_abs_checked(-10).unwrap(), 10);
+        assert_eq!(
+            libc_result::c_abs_checked(1 << 40).unwrap_err().to_string(),
+            "c_abs_checked() failed: argument `x` cannot be converted to `i32`: \
+             out of range integral type conversion attempted"
+        );
+        assert_eq!(libc_result::c_abs_widened(-11).unwrap(), 11);
+        assert_eq!(libc_result::c_abs_lossy((1 << 32) - 12).unwrap(), 12);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_into_ffi_arg() {
+        assert_eq!(libc_convert::c_labs_negated(libc_convert::Negated(12)), 12);
+        let mut buffer = vec![0u8; 4];
+        libc_convert::c_memset(&mut buffer, b'x' as i32, 3);
+        assert_eq!(buffer, b"xxx\0");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_callback() {
+        use std::ffi::c_void;
+
+        let mut values = [3i32, 1, 2];
+        let mut calls = 0;
+        libc_callback::c_qsort_r(
+            values.as_mut_ptr().cast(),
+            values.len(),
+            size_of::<i32>(),
+            |a, b| {
+                calls += 1;
+                unsafe { (*a.cast::<i32>()).cmp(&*b.cast::<i32>()) as i32 }
+            },
+        );
+        assert_eq!(values, [1, 2, 3]);
+        assert!(calls > 0);
+
+        // コールバック内のパニックは呼び出し元で再開される
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            libc_callback::c_qsort_r(
+                values.as_mut_ptr().cast(),
+                values.len(),
+                size_of::<i32>(),
+                |_: *const c_void, _: *const c_void| panic!("compare failed"),
+            )
+        }));
+        assert_eq!(
+            panic.unwrap_err().downcast_ref::<&str>(),
+            Some(&"compare failed")
+        );
+
+        let (result, registration) = libc_callback::c_on_exit(|_status| {});
+        assert_eq!(result, 0);
+        assert!(registration.take_panic().is_none());
+        registration.leak();
+    }
+
+    #[test]
+    fn test_callback_userdata_position() {
+        let mut calls = 0;
+        let result = local_callback::apply(
+            |x| {
+                calls += 1;
+                x * 2
+            },
+            21,
+        );
+        assert_eq!((result, calls), (42, 1));
+
+        // 実行中のクロージャへの再入は呼び出さず、panic_value()(0)を返す
+        assert_eq!(
+            local_callback::apply(|x| local_callback::reenter(x) + 1, 5),
+            1
+        );
+    }
+
+    #[test]
+    fn test_callback_static_slots_reentry() {
+        // 実行中のスロットへの再入はデッドロックせず、panic_value()(0)を返す
+        assert_eq!(
+            local_callback::apply_hook(|x| local_callback::reenter_hook(x) + 1, 5),
+            1
+        );
+        // 実行中のクロージャから別のスロットを登録できる
+        assert_eq!(
+            local_callback::apply_hook(|x| local_callback::apply_hook(|y| y * 3, x) + 1, 5),
+            16
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_callback_static_slots() {
+        // 呼び出し中のみ使用するスロットは、呼び出し後に解放される
+        for _ in 0..2 {
+            let mut values = [3i32, 1, 2];
+            libc_callback::c_qsort(
+                values.as_mut_ptr().cast(),
+                values.len(),
+                size_of::<i32>(),
+                |a, b| unsafe { (*b.cast::<i32>()).cmp(&*a.cast::<i32>()) as i32 },
+            );
+            assert_eq!(values, [3, 2, 1]);
+        }
+
+        // 登録したスロットはCallbackRegistrationを破棄するまで使用中となる
+        let (result, registration) = libc_callback::c_atexit(|| {}).unwrap();
+        assert_eq!(result, 0);
+        assert!(matches!(
+            libc_callback::c_atexit(|| {}),
+            Err(crate::FfiError::CallbackSlotsExhausted { slots: 1, .. })
+        ));
+        drop(registration);
+        let (_, registration) = libc_callback::c_atexit(|| {}).unwrap();
+        registration.leak();
+    }
+
+    #[test]
+    fn test_reference_args() {
+        // &Tへの独自のIntoFfiArgの実装が使用される
+        assert_eq!(local_convert::magnitude(&local_convert::Magnitude(-8)), 8);
+        assert_eq!(local_convert::strlen_or_null(Some("abc")), 3);
+        assert_eq!(local_convert::strlen_or_null(None), -1);
+        assert_eq!(local_convert::strlen_c_str(c"abcd"), 4);
+    }
+
+    #[test]
+    fn test_impl_trait_args() {
+        use crate::build::Builder;
+        assert_eq!(local_convert::sum_bytes([1u8, 2, 3], 3), 6);
+        assert_eq!(local_convert::strlen_display(&12345), 5);
+
+        // Resultを返す場合、NULを含む文字列はパニックせずにErrとなる
+        assert_eq!(local_convert::strlen_checked("abc").unwrap(), 3);
+        assert!(matches!(
+            local_convert::strlen_checked("a\0b"),
+            Err(crate::FfiError::InvalidArgument { arg: "s", .. })
+        ));
+
+        // 変換できないimpl Traitは引数を指すエラーとする
+        let error = Builder::new()
+            .config("#[arg_convert]")
+            .function("fn strlen(s: &mut impl AsMut<str>) -> usize")
+            .generate()
+            .unwrap_err();
+        assert!(error.to_string().contains("`&mut impl AsMut<str>`"));
+        assert!(error.to_string().contains("use `&mut String`"));
+        let error = Builder::new()
+            .config("#[arg_convert]")
+            .function("fn strlen(s: impl Iterator<Item = u8>) -> usize")
+            .generate()
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("unsupported argument type `impl Iterator<Item = u8>`")
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_struct_args() {
+        let mut tv = libc_struct::TimeVal::default();
+        assert_eq!(libc_struct::c_gettimeofday(&mut tv, None), 0);
+        assert!(tv.tv_sec > 0);
+        let mut t = 0;
+        let now = libc_struct::c_time(Some(&mut t));
+        assert_eq!(t, now);
+        assert!(libc_struct::c_time(None) >= now);
+        assert_eq!(libc_struct::c_abs_value(libc_struct::Value(-13)), 13);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_runtime_library() {
+        use libc_runtime::CLibrary;
+        // libc.soはリンカスクリプトのため、実体のファイル名で読み込む
+        let library = CLibrary::load_from("libc.so.6").unwrap();
+        assert_eq!(library.abs(-3).unwrap(), 3);
+        assert_eq!(library.c_close(-1).unwrap_err().symbol(), Some("close"));
+        assert!(matches!(
+            library.cffi_gen_missing_symbol(),
+            Err(crate::FfiError::SymbolUnavailable { .. })
+        ));
+        assert!(library.resolve_all().is_err());
+
+        let _ = CLibrary::set_global(library);
+        assert_eq!(libc_runtime::abs(-5).unwrap(), 5);
+
+        assert!(matches!(
+            CLibrary::load_from("libcffi_gen_missing.so"),
+            Err(crate::FfiError::LibraryUnavailable { .. })
+        ));
+
+        // ライブラリ構造体のメソッドと同名のラップ関数は#[alias]での変更を求める
+        use crate::build::Builder;
+        let runtime = Builder::new().config(r#"#[library_name = "c"] #[link_type = "runtime"]"#);
+        let error = runtime
+            .clone()
+            .function("fn load() -> i32")
+            .generate()
+            .unwrap_err();
+        assert!(error.to_string().contains(
+            "`load` conflicts with a method of the generated library struct; rename the wrapper with `#[alias = ...]`"
+        ));
+        assert!(
+            runtime
+                .function(r#"#[alias = "c_load"] fn load() -> i32"#)
+                .generate()
+                .is_ok()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_passthrough_attributes() {
+        let library = libc_passthrough::CLibrary::load_from("libc.so.6").unwrap();
+        assert_eq!(library.abs(-3).unwrap(), 3);
+        #[allow(deprecated)]
+        let value = library.labs(-4).unwrap();
+        assert_eq!(value, 4);
+
+        use crate::build::Builder;
+        let source = Builder::new()
+            .library("c")
+            .function("#[cfg(windows)] #[allow(non_snake_case)] fn Abs(x: i32) -> i32")
+            .generate()
+            .unwrap();
+        assert!(source.contains("#[cfg(windows)]\n#[link(name = \"c\")]\nunsafe extern \"C\" {"));
+        assert!(
+            source
+                .contains("#[cfg(windows)]\n#[allow(non_snake_case)]\npub fn Abs(x: i32) -> i32 {")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_def_file() {
+        assert!(!dxlib_def::dxlib_init_is_available());
+        assert!(!dxlib_def::dxlib_end_is_available());
+        assert!(dxlib_def::DEF_UNDECLARED_EXPORTS.contains(&"dx_ProcessMessage"));
+        assert!(!dxlib_def::DEF_UNDECLARED_EXPORTS.contains(&"dx_DxLib_Init"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_header() {
+        use libc_header::*;
+        assert_eq!(c_abs(-3), 3);
+        assert_eq!(strlen("cffi"), 4);
+        assert_eq!(atoi("42"), 42);
+        assert_eq!((CFFI_MAX_PATH, CFFI_FLAGS, CFFI_NEGATIVE), (260, 17, -2));
+        assert_eq!(CFFI_BIG, 0x1_0000_0000);
+        // u64に収まらない値はi128とする
+        assert_eq!(
+            (CFFI_HUGE, CFFI_HUGE_NEGATIVE),
+            (1i128 << 70, -(1i128 << 70))
+        );
+        assert_eq!((CFFI_MODE_NONE, CFFI_MODE_SLOW, CFFI_GREEN), (0, 4, 2));
+
+        let shape = CFFI_SHAPE {
+            origin: CFFI_POINT { x: 1, y: 2 },
+            name: [0; CFFI_MAX_PATH as usize],
+            label: std::ptr::null(),
+        };
+        assert_eq!(shape.origin.y, 2);
+
+        unsafe extern "C" fn compare(
+            a: *const std::ffi::c_void,
+            b: *const std::ffi::c_void,
+        ) -> i32 {
+            unsafe { *(a as *const i32) - *(b as *const i32) }
+        }
+        let mut values = [3, 1, 2];
+        let compare: CFFI_COMPARE = Some(compare);
+        qsort(
+            values.as_mut_ptr().cast(),
+            values.len(),
+            size_of::<i32>(),
+            compare,
+        );
+        assert_eq!(values, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_build_api() {
+        use crate::build::{BuildError, Builder};
+        let source = Builder::new()
+            .library("c")
+            .config("#[as_result]")
+            .function(r#"#[alias = "c_abs"] fn abs(x: i32) -> i32,"#)
+            .generate()
+            .unwrap();
+        assert!(source.starts_with("// @generated"));
+        assert!(source.contains(
+            "pub fn c_abs(x: i32) -> ::std::result::Result<i32, ::cffi_gen::FfiError> {"
+        ));
+
+        // #[link_cfg]の条件を満たす場合のみリンク指定を行う
+        let source = Builder::new()
+            .config(
+                r#"#[library_name = "DxLib_x64"] #[link_type = "dylib"] #[link_cfg = "windows"]"#,
+            )
+            .function("fn DxLib_Init() -> i32")
+            .generate()
+            .unwrap();
+        assert!(source.contains(
+            "#[cfg_attr(windows, link(name = \"DxLib_x64\", kind = \"dylib\"))]\nunsafe extern \"C\" {"
+        ));
+
+        let error = Builder::new()
+            .config("#[as_reslt]")
+            .function("fn abs(x: i32) -> i32")
+            .generate()
+            .unwrap_err();
+        assert!(matches!(error, BuildError::Generate(_)));
+        assert!(error.to_string().contains("did you mean `as_result`?"));
+        // 未実装のアトリビュートは無視せずにエラーとする
+        let error = Builder::new()
+            .config("#[not_null_assert = false]")
+            .function("fn abs(x: i32) -> i32")
+            .generate()
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("`not_null_assert` is not supported yet")
+        );
+        assert!(matches!(
+            Builder::new().function("fn abs(").generate(),
+            Err(BuildError::Parse { .. })
+        ));
+
+        let builder = Builder::new()
+            .spec(r#"cffi_gen! { config{ #[library_name = "c"] } functions{ fn labs(x: i64) -> i64, } }"#)
+            .header("tests/libc_subset.h");
+        let (_, functions) = builder.declarations().unwrap();
+        let names = functions
+            .iter()
+            .map(|f| f.sig.ident.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names[..2], ["abs", "labs"]);
+        assert!(names.contains(&"qsort".to_string()) && !names.contains(&"printf".to_string()));
+        let source = builder.generate().unwrap();
+        assert!(source.contains("pub type CFFI_POINT = tagCFFI_POINT;"));
+        // 入力ファイルの絶対パスは生成ファイルに含めない(cargo:rerun-if-changedで再実行させる)
+        assert!(!source.contains("include_bytes!"));
+        let source = Builder::new()
+            .config(r#"#[library_name = "DxLib_x64"] #[def_file = "DxLib_x64.def"]"#)
+            .function(r#"#[func_name = "dx_DxLib_Init"] fn DxLib_Init() -> i32"#)
+            .generate()
+            .unwrap();
+        assert!(!source.contains("include_bytes!") && !source.contains("DxLib_x64.def"));
+    }
 }
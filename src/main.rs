@@ -0,0 +1,535 @@
+// =====================================================================
+// cffi-genコマンド
+// cffi_gen!の本体、.defファイル、Cヘッダーから生成されるRustのコードを確認する
+// (cargo expandを使わずに、ラップ関数の形や引数の変換方法を確認するためのもの)
+// =====================================================================
+use anyhow::{Context, Result, bail};
+use cffi_gen::build::Builder;
+use cffi_gen_core::cffi_analyzer::CFFIAnalyzer;
+use cffi_gen_core::cffi_attribute_analyzer::CFFIAttributeAnalyzer;
+use cffi_gen_core::cffi_def_file::CFFIDefFile;
+use cffi_gen_core::cffi_header::{CFFIHeaderParser, CHeaderOptions};
+use cffi_gen_core::cffi_model::{ArgConversion, CFFIModel, CallbackContext};
+use cffi_gen_core::defines::{M_ATTR_DEF_FILE, M_ATTR_DEF_REPORT_UNDECLARED};
+use quote::ToTokens;
+use similar::TextDiff;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use syn::LitStr;
+
+const USAGE: &str = "\
+usage: cffi-gen <command> [options] <spec>...
+
+commands:
+  generate <spec>   print the generated Rust code (a `.def` prints a cffi_gen! template)
+  symbols <spec>    list declared symbols against the exports of a `.def` file
+  args <spec>       show the resolved conversion of each argument and return value
+  diff <a> <b>      diff the generated code of two specs or generated files
+
+<spec> is a file containing a cffi_gen! body (`config{...} functions{...}`),
+a spec file (`.toml`, `.json`), a `.def` file, or a C header (`.h`).
+
+options:
+  -o, --output <path>   write the output to a file instead of stdout
+  --library <name>      add `#[library_name = name]` to config{}
+  --config <attrs>      add attributes to config{} (e.g. \"#[as_result]\")
+  --def <path>          `.def` file for `symbols` (defaults to `#[def_file]`)
+  -h, --help            print this message";
+
+// 生成済みのファイルの先頭行(diffで生成せずにそのまま比較する)
+const GENERATED_MARKER: &str = "// @generated";
+
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1).collect()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+// =====================================================================
+// コマンドライン引数
+// =====================================================================
+#[derive(Default)]
+struct Options {
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    config: Vec<String>,
+    def: Option<PathBuf>,
+}
+
+impl Options {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .with_context(|| format!("`{name}` requires a value"))
+            };
+            match arg.as_str() {
+                "-o" | "--output" => options.output = Some(value(&arg)?.into()),
+                "--library" => {
+                    let name = value(&arg)?;
+                    options.config.push(format!("#[library_name = {name:?}]"));
+                }
+                "--config" => options.config.push(value(&arg)?),
+                "--def" => options.def = Some(value(&arg)?.into()),
+                option if option.starts_with('-') => bail!("unknown option `{option}`"),
+                _ => options.inputs.push(arg.into()),
+            }
+        }
+        Ok(options)
+    }
+
+    // =====================================================================
+    // 入力ファイルが指定の数だけあることを確認して返す関数
+    // =====================================================================
+    fn inputs<const N: usize>(&self) -> Result<[&Path; N]> {
+        let inputs = self.inputs.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+        inputs
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("expected {N} input file(s)\n\n{USAGE}"))
+    }
+
+    // =====================================================================
+    // 出力先(指定がない場合は標準出力)に書き出す関数
+    // =====================================================================
+    fn write(&self, text: &str) -> Result<()> {
+        match &self.output {
+            Some(path) => std::fs::write(path, text)
+                .with_context(|| format!("failed to write `{}`", path.display())),
+            // パイプ先(head等)が先に終了した場合はエラーにしない
+            None => match std::io::stdout().write_all(text.as_bytes()) {
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e.into()),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+fn run(args: Vec<String>) -> Result<ExitCode> {
+    let Some((command, args)) = args.split_first() else {
+        bail!("no command given\n\n{USAGE}");
+    };
+    if matches!(command.as_str(), "-h" | "--help" | "help")
+        || args.iter().any(|a| a == "-h" || a == "--help")
+    {
+        println!("{USAGE}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let options = Options::parse(args.iter().cloned())?;
+    match command.as_str() {
+        "generate" => {
+            let [input] = options.inputs()?;
+            options.write(&generate(input, &options)?)?;
+        }
+        "symbols" => {
+            let [input] = options.inputs()?;
+            options.write(&symbols(input, &options)?)?;
+        }
+        "args" => {
+            let [input] = options.inputs()?;
+            options.write(&args_report(input, &options)?)?;
+        }
+        "diff" => {
+            let [old, new] = options.inputs()?;
+            let old_text = generated_text(old, &options)?;
+            let new_text = generated_text(new, &options)?;
+            let diff = diff(old, &old_text, new, &new_text);
+            options.write(&diff)?;
+            if !diff.is_empty() {
+                return Ok(ExitCode::from(1));
+            }
+        }
+        _ => bail!("unknown command `{command}`\n\n{USAGE}"),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// =====================================================================
+// 入力ファイルの種類(拡張子で判定する)
+// =====================================================================
+enum SpecKind {
+    Body,
+    Def,
+    Header,
+    SpecFile,
+}
+
+impl SpecKind {
+    fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("def") => SpecKind::Def,
+            Some("h") => SpecKind::Header,
+            Some("toml" | "json") => SpecKind::SpecFile,
+            _ => SpecKind::Body,
+        }
+    }
+
+    fn is_body(&self) -> bool {
+        matches!(self, SpecKind::Body)
+    }
+}
+
+fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("failed to read `{}`", path.display()))
+}
+
+// =====================================================================
+// 入力ファイルとオプションからBuilderを作成する関数
+// (Builderは相対パスをCARGO_MANIFEST_DIRからのパスとするため、カレントディレクトリからの絶対パスにする)
+// =====================================================================
+fn builder(input: &Path, options: &Options) -> Result<Builder> {
+    let path = std::path::absolute(input)
+        .with_context(|| format!("failed to resolve `{}`", input.display()))?;
+    let builder = match SpecKind::of(input) {
+        SpecKind::Body => Builder::new().spec(&read(&path)?),
+        SpecKind::Header => {
+            report_skipped(&path)?;
+            Builder::new().header(&path)
+        }
+        SpecKind::SpecFile => {
+            Builder::new().spec(&format!("spec = {:?}", path.display().to_string()))
+        }
+        SpecKind::Def => bail!(
+            "`{}` only contains symbol names; use `cffi-gen generate` to print a template",
+            input.display()
+        ),
+    };
+    Ok(options
+        .config
+        .iter()
+        .fold(builder, |builder, attrs| builder.config(attrs)))
+}
+
+// =====================================================================
+// ヘッダーのうち、解析できずに読み飛ばした宣言を警告として出力する関数
+// =====================================================================
+fn report_skipped(path: &Path) -> Result<()> {
+    let header = CFFIHeaderParser::parse(&read(path)?, &CHeaderOptions::default());
+    for declaration in &header.skipped {
+        eprintln!("warning: skipped `{declaration}` in `{}`", path.display());
+    }
+    Ok(())
+}
+
+// =====================================================================
+// generate : 生成されるコード(.defの場合はcffi_gen!のひな形)を返す関数
+// =====================================================================
+fn generate(input: &Path, options: &Options) -> Result<String> {
+    if let SpecKind::Def = SpecKind::of(input) {
+        return def_template(input, options);
+    }
+    Ok(builder(input, options)?.generate()?)
+}
+
+// =====================================================================
+// .defファイルのEXPORTSからcffi_gen!のひな形を作成する関数
+// (.defにはシンボル名しかないため、引数と戻り値はコメントとして出力する)
+// =====================================================================
+fn def_template(input: &Path, options: &Options) -> Result<String> {
+    let def_file = load_def(input)?;
+    let library = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut text = String::from("cffi_gen! {\n    config{\n");
+    if !options
+        .config
+        .iter()
+        .any(|attrs| attrs.contains("library_name"))
+    {
+        text += &format!("        #[library_name = {library:?}],\n");
+    }
+    for attrs in &options.config {
+        text += &format!("        {attrs},\n");
+    }
+    text += &format!(
+        "        #[def_file = {:?}],\n    }}\n    functions{{\n",
+        input.display().to_string()
+    );
+    for export in &def_file.exports {
+        text += &format!("        // fn {export}(/* arguments */) -> /* return type */,\n");
+    }
+    text += "    }\n}\n";
+    Ok(text)
+}
+
+fn load_def(path: &Path) -> Result<CFFIDefFile> {
+    let path = std::path::absolute(path)
+        .with_context(|| format!("failed to resolve `{}`", path.display()))?;
+    let lit = syn::parse_str::<LitStr>(&format!("{:?}", path.display().to_string()))?;
+    Ok(CFFIDefFile::load(&lit)?)
+}
+
+// =====================================================================
+// config{}と関数宣言からモデルを構築する関数
+// (.defファイルとの照合はsymbolsで行うため、#[def_file]等は取り除く)
+// =====================================================================
+fn model(input: &Path, options: &Options) -> Result<(CFFIModel, Option<LitStr>)> {
+    let (mut config_attrs, functions) = builder(input, options)?.declarations()?;
+    let def_file = CFFIAttributeAnalyzer::get_str(&config_attrs, M_ATTR_DEF_FILE)?;
+    config_attrs.retain(|attr| {
+        !attr.path().is_ident(M_ATTR_DEF_FILE)
+            && !attr.path().is_ident(M_ATTR_DEF_REPORT_UNDECLARED)
+    });
+    let model = CFFIModel::build(&config_attrs, &functions)?;
+    Ok((model, def_file))
+}
+
+// =====================================================================
+// symbols : 宣言された関数のシンボル名と.defファイルのEXPORTSを照合した結果を返す関数
+// =====================================================================
+fn symbols(input: &Path, options: &Options) -> Result<String> {
+    let (declared, def_file) = match SpecKind::of(input) {
+        SpecKind::Def => (Vec::new(), Some(load_def(input)?)),
+        _ => {
+            let (model, def_lit) = model(input, options)?;
+            let declared = model
+                .functions
+                .iter()
+                .map(|function| {
+                    (
+                        function.link_name.clone(),
+                        function.wrapper_name.to_string(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            let def_file = match (&options.def, def_lit) {
+                (Some(path), _) => Some(load_def(path)?),
+                (None, Some(lit)) => Some(CFFIDefFile::load(&lit)?),
+                (None, None) => None,
+            };
+            (declared, def_file)
+        }
+    };
+
+    let mut text = String::new();
+    let exports = def_file.as_ref().map(|def_file| &def_file.exports);
+    text += &format!("declared ({}):\n", declared.len());
+    for (link_name, wrapper_name) in &declared {
+        let status = match exports {
+            Some(exports) if exports.contains(link_name) => "  exported",
+            Some(_) => "  NOT EXPORTED",
+            None => "",
+        };
+        text += &format!("  {link_name} -> {wrapper_name}(){status}\n");
+    }
+    if let Some(def_file) = &def_file {
+        let undeclared =
+            def_file.undeclared(declared.iter().map(|(link_name, _)| link_name.as_str()));
+        text += &format!(
+            "\nexported by `{}` but not declared ({}):\n",
+            def_file.name,
+            undeclared.len()
+        );
+        for export in undeclared {
+            text += &format!("  {export}\n");
+        }
+    }
+    Ok(text)
+}
+
+// =====================================================================
+// args : 各関数の引数、戻り値の変換方法を返す関数
+// =====================================================================
+fn args_report(input: &Path, options: &Options) -> Result<String> {
+    let (model, _) = model(input, options)?;
+    let mut text = String::new();
+    for function in &model.functions {
+        text += &format!(
+            "fn {} (symbol `{}`, abi \"{}\"{})\n",
+            function.wrapper_name,
+            function.link_name,
+            function.abi.value(),
+            if function.optional { ", optional" } else { "" },
+        );
+        for arg in &function.args {
+            let conversion = match &arg.conversion {
+                ArgConversion::PassThrough => "passed as-is".to_string(),
+                ArgConversion::OptionDefault(expr) => {
+                    format!("None is passed as `{}`", compact(expr))
+                }
+                ArgConversion::Callback(callback) => format!(
+                    "closure passed through {}{}",
+                    match &callback.context {
+                        CallbackContext::Userdata { arg, position } => {
+                            format!("a trampoline with userdata `{arg}` (C parameter #{position})")
+                        }
+                        CallbackContext::StaticSlots(slots) => {
+                            format!("one of {slots} static trampoline slots")
+                        }
+                    },
+                    if callback.registered {
+                        ", kept alive by the returned CallbackRegistration"
+                    } else {
+                        ""
+                    }
+                ),
+                ArgConversion::CallbackUserdata => {
+                    "set to the callback state (hidden from the wrapper)".to_string()
+                }
+                ArgConversion::IntoFfi { source, .. } => {
+                    format!("converted with `IntoFfiArg::into_ffi({})`", compact(source))
+                }
+                ArgConversion::TryInto(ty) => {
+                    format!("converted with TryFrom into `{}`", compact(ty))
+                }
+                ArgConversion::Lossy(ty) => {
+                    format!("converted with `as {}` (lossy)", compact(ty))
+                }
+            };
+            text += &format!(
+                "    {}: {} -> {} ({conversion})\n",
+                arg.ident,
+                compact(&arg.wrapper_ty),
+                compact(&arg.extern_ty),
+            );
+        }
+        let ret = compact(&function.ret.ty);
+        let fallible =
+            function.error_policy.as_result || function.optional || model.library.runtime.is_some();
+        let ret = match fallible {
+            true => format!(
+                "Result<{ret}, {}>",
+                compact(&function.error_policy.error_type)
+            ),
+            false => ret,
+        };
+        let condition = match &function.error_policy.condition {
+            Some(condition) if function.error_policy.as_result => {
+                format!(" (error when `{}`)", compact(condition))
+            }
+            _ => String::new(),
+        };
+        text += &format!("    -> {ret}{condition}\n\n");
+    }
+    Ok(text)
+}
+
+// =====================================================================
+// トークン列を読みやすい文字列にする関数(`:: std :: os` -> `::std::os`等)
+// =====================================================================
+fn compact(tokens: impl ToTokens) -> String {
+    CFFIAnalyzer::tokens_to_string(tokens)
+}
+
+// =====================================================================
+// diffの入力を生成したコードにする関数(生成済みのファイルはそのまま使用する)
+// =====================================================================
+fn generated_text(input: &Path, options: &Options) -> Result<String> {
+    if SpecKind::of(input).is_body() {
+        let text = read(input)?;
+        if text.starts_with(GENERATED_MARKER) {
+            return Ok(text);
+        }
+    }
+    generate(input, options)
+}
+
+// =====================================================================
+// 行単位の差分をunified形式で返す関数(差分がない場合は空文字列)
+// (Myersの差分アルゴリズムのため、大きなファイルでも線形のメモリで比較できる)
+// =====================================================================
+fn diff(old_path: &Path, old: &str, new_path: &Path, new: &str) -> String {
+    // 変更行の前後3行を含む範囲をまとめて出力する
+    const CONTEXT: usize = 3;
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(CONTEXT)
+        .header(
+            &old_path.display().to_string(),
+            &new_path.display().to_string(),
+        )
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // テスト用のcffi_gen!の本体
+    const LIBC_SPEC: &str = r#"
+config{
+    #[library_name = "c"],
+    #[as_result],
+    #[arg_convert],
+}
+functions{
+    #[alias = "c_abs"] #[as_result = false] fn abs(x: i32) -> i32,
+    #[as_result = false] fn strlen(s: &str) -> usize,
+    #[alias = "c_close"] #[error_with_args] fn close(fd: i32) -> i32,
+}
+"#;
+
+    // =====================================================================
+    // LIBC_SPECを一時ディレクトリに書き出し、パスを返す関数
+    // =====================================================================
+    fn write_spec(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("cffi_gen_cli_{}_{name}.rs", std::process::id()));
+        std::fs::write(&path, LIBC_SPEC).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_diff() {
+        let (old, new) = (Path::new("old.rs"), Path::new("new.rs"));
+        assert_eq!(diff(old, "a\nb\nc\n", new, "a\nb\nc\n"), "");
+        assert_eq!(
+            diff(old, "a\nb\nc\nd\n", new, "a\nB\nc\nd\ne\n"),
+            "--- old.rs\n+++ new.rs\n@@ -1,4 +1,5 @@\n a\n-b\n+B\n c\n d\n+e\n"
+        );
+
+        // 離れた変更は別のhunkとし、大きな入力でも比較できる
+        let old_text = (0..100_000).map(|i| format!("{i}\n")).collect::<String>();
+        let new_text = old_text.replacen("10\n", "ten\n", 1) + "end\n";
+        let text = diff(old, &old_text, new, &new_text);
+        assert!(text.contains("@@ -8,7 +8,7 @@\n 7\n 8\n 9\n-10\n+ten\n 11\n"));
+        assert!(text.ends_with(" 99999\n+end\n"));
+    }
+
+    #[test]
+    fn test_symbols() {
+        let def_path =
+            std::env::temp_dir().join(format!("cffi_gen_cli_{}.def", std::process::id()));
+        std::fs::write(
+            &def_path,
+            "LIBRARY c.dll\n\nEXPORTS\n\tabs\n\tstrlen\n\tlabs\n",
+        )
+        .unwrap();
+        let options = Options {
+            def: Some(def_path.clone()),
+            ..Options::default()
+        };
+        let spec_path = write_spec("symbols");
+        let text = symbols(&spec_path, &options).unwrap();
+        std::fs::remove_file(&def_path).unwrap();
+        std::fs::remove_file(&spec_path).unwrap();
+        assert_eq!(
+            text,
+            format!(
+                "declared (3):\n  abs -> c_abs()  exported\n  strlen -> strlen()  exported\n  \
+                 close -> c_close()  NOT EXPORTED\n\nexported by `{}` but not declared (1):\n  labs\n",
+                def_path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_args_report() {
+        let spec_path = write_spec("args");
+        let text = args_report(&spec_path, &Options::default()).unwrap();
+        std::fs::remove_file(&spec_path).unwrap();
+        assert!(text.contains(
+            "    x: i32 -> <i32 as ::cffi_gen::IntoFfiArg>::CType (converted with `IntoFfiArg::into_ffi(x)`)\n"
+        ));
+        assert!(
+            text.contains("    -> Result<i32, ::cffi_gen::FfiError> (error when `result == -1`)\n")
+        );
+    }
+}
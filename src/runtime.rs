@@ -0,0 +1,181 @@
+use crate::FfiError;
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, OnceLock};
+
+// Library::sharedで読み込んだライブラリ(プロセス終了まで解放しない)
+static SHARED_LIBRARIES: Mutex<Vec<(Option<&'static str>, &'static Library)>> =
+    Mutex::new(Vec::new());
+
+// =====================================================================
+// #[link_type = "runtime"]で生成されるライブラリ構造体が使用する、
+// 実行時に読み込んだ共有ライブラリ(dlopen/LoadLibrary)
+// =====================================================================
+pub struct Library {
+    library: libloading::Library,
+    // エラーメッセージ用のパス
+    path: String,
+    // 指定のライブラリを読み込めず、実行中のプロセスで代用した場合の読み込みエラー
+    // (ライブラリ名, 理由)(シンボルが見つからない場合はこちらをエラーとして返す)
+    load_error: Option<(String, String)>,
+}
+
+impl Library {
+    // =====================================================================
+    // 指定パスの共有ライブラリを読み込む関数
+    // =====================================================================
+    pub fn open(path: impl AsRef<OsStr>) -> Result<Self, FfiError> {
+        let path = path.as_ref();
+        // 読み込み時に初期化処理が実行されるため、呼び出し側の責任で読み込む
+        let library = unsafe { libloading::Library::new(path) }.map_err(|e| {
+            FfiError::LibraryUnavailable {
+                library: path.to_string_lossy().into_owned(),
+                reason: e.to_string(),
+            }
+        })?;
+        Ok(Library {
+            library,
+            path: path.to_string_lossy().into_owned(),
+            load_error: None,
+        })
+    }
+
+    // =====================================================================
+    // 実行中のプロセス自体(リンク済みのライブラリを含む)をライブラリとして返す関数
+    // =====================================================================
+    pub fn this() -> Result<Self, FfiError> {
+        let path = "<current process>".to_string();
+        #[cfg(unix)]
+        let library = libloading::os::unix::Library::this();
+        #[cfg(windows)]
+        let library =
+            libloading::os::windows::Library::this().map_err(|e| FfiError::LibraryUnavailable {
+                library: path.clone(),
+                reason: e.to_string(),
+            })?;
+        Ok(Library {
+            library: library.into(),
+            path,
+            load_error: None,
+        })
+    }
+
+    // =====================================================================
+    // #[optional]の関数が使用する、プロセス全体で共有するライブラリを返す関数
+    // (初回のみ読み込み、以降は同じライブラリを返す)
+    // ライブラリ名のファイルが読み込めない場合は、既にリンクされているものとして実行中のプロセスから探す
+    // (その場合、シンボルが見つからなければ元の読み込みエラーを返す)
+    // (ライブラリ名がNoneの場合は実行中のプロセスのみ)
+    // =====================================================================
+    pub fn shared(name: Option<&'static str>) -> Result<&'static Library, FfiError> {
+        let find = || {
+            let libraries = SHARED_LIBRARIES.lock().unwrap_or_else(|e| e.into_inner());
+            libraries
+                .iter()
+                .find(|(shared, _)| *shared == name)
+                .map(|(_, library)| *library)
+        };
+        if let Some(library) = find() {
+            return Ok(library);
+        }
+
+        // 読み込み時の初期化処理でロックを保持しないよう、ロックの外で読み込む
+        let library = match name {
+            Some(name) => match Library::open(library_filename(name)) {
+                Ok(library) => library,
+                Err(FfiError::LibraryUnavailable { library, reason }) => Library {
+                    load_error: Some((library, reason)),
+                    ..Library::this()?
+                },
+                Err(e) => return Err(e),
+            },
+            None => Library::this()?,
+        };
+
+        // 他のスレッドが先に読み込んだ場合はそちらを使用する
+        let mut libraries = SHARED_LIBRARIES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, library)) = libraries.iter().find(|(shared, _)| *shared == name) {
+            return Ok(library);
+        }
+        let library: &'static Library = Box::leak(Box::new(library));
+        libraries.push((name, library));
+        Ok(library)
+    }
+
+    // =====================================================================
+    // 読み込んだライブラリのパスを返す関数
+    // =====================================================================
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // =====================================================================
+    // シンボルを関数ポインタ等として取得する関数
+    // (Tはシンボルの実際の型と一致している必要がある)
+    // =====================================================================
+    /// # Safety
+    /// Tはシンボルの実際の型(関数ポインタのシグネチャ等)と一致していること
+    pub unsafe fn symbol<T: Copy>(&self, name: &'static str) -> Result<T, FfiError> {
+        unsafe { self.library.get::<T>(name.as_bytes()) }
+            .map(|symbol| *symbol)
+            .map_err(|_| self.missing_symbol(name))
+    }
+
+    // =====================================================================
+    // シンボルが見つからない場合のエラーを返す関数
+    // (実行中のプロセスで代用している場合は、指定ライブラリの読み込みエラーとする)
+    // =====================================================================
+    fn missing_symbol(&self, name: &'static str) -> FfiError {
+        match &self.load_error {
+            Some((library, reason)) => FfiError::LibraryUnavailable {
+                library: library.clone(),
+                reason: reason.clone(),
+            },
+            None => FfiError::SymbolUnavailable {
+                library: self.path.clone(),
+                symbol: name,
+            },
+        }
+    }
+}
+
+// =====================================================================
+// 初回使用時に解決し、以降は結果を保持するシンボル
+// (存在しないシンボルも、存在しないという結果を保持する)
+// =====================================================================
+pub struct LazySymbol<T> {
+    cell: OnceLock<Option<T>>,
+}
+
+impl<T: Copy> LazySymbol<T> {
+    pub const fn new() -> Self {
+        LazySymbol {
+            cell: OnceLock::new(),
+        }
+    }
+
+    // =====================================================================
+    // シンボルを取得する関数(未解決の場合は指定ライブラリから解決する)
+    // =====================================================================
+    /// # Safety
+    /// Tはシンボルの実際の型と一致し、常に同じライブラリ、シンボル名で呼び出すこと
+    pub unsafe fn get(&self, library: &Library, name: &'static str) -> Result<T, FfiError> {
+        let symbol = self
+            .cell
+            .get_or_init(|| unsafe { library.symbol::<T>(name) }.ok());
+        symbol.ok_or_else(|| library.missing_symbol(name))
+    }
+}
+
+impl<T: Copy> Default for LazySymbol<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// =====================================================================
+// ライブラリ名からプラットフォームごとのファイル名を返す関数
+// ("DxLib_x64" -> "DxLib_x64.dll"、"c" -> "libc.so"等)
+// =====================================================================
+pub fn library_filename(name: &str) -> OsString {
+    libloading::library_filename(name)
+}
@@ -1,8 +1,9 @@
-use std::ffi::CString;
+use crate::FfiError;
+use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
+
 pub struct CStringHolder {
     _c_string: CString,
-    ptr: *const c_char,
 }
 
 impl CStringHolder {
@@ -10,7 +11,6 @@ impl CStringHolder {
         let c_string = CString::new(s.to_string()).unwrap();
         Self {
             _c_string: c_string,
-            ptr: std::ptr::null_mut(),
         }
     }
 
@@ -19,26 +19,366 @@ impl CStringHolder {
     }
 }
 
-// ポインター型のラップ
-pub enum RawPointer<T> {
-    Mutable(*mut T),
-    Constant(*const T),
+// =====================================================================
+// ラップ関数の引数をextern宣言側の型に変換するトレイト
+// (#[arg_convert]の場合、引数は`IntoFfiArg::into_ffi(x)`で変換される)
+//
+// 独自の型に実装することで、マクロを変更せずに引数の型として使用できる
+// impl IntoFfiArg for Color {
+//     type CType = u32;
+//     type Guard = ();
+//     fn into_ffi(self) -> (Self::CType, Self::Guard) {
+//         (self.to_rgb(), ())
+//     }
+// }
+// =====================================================================
+pub trait IntoFfiArg {
+    // extern宣言側の型
+    type CType;
+    // 呼び出しが終わるまで一時オブジェクト(CString等)を保持する型
+    type Guard;
+
+    // C側の値と、呼び出しの間保持するガードを返す関数
+    // (C側の値がガード内のデータを指す場合、ガードの移動で無効にならないこと)
+    fn into_ffi(self) -> (Self::CType, Self::Guard);
+
+    // 変換に失敗する場合にエラーを返す関数(ラップ関数がResultを返す場合に使用される)
+    // (失敗しうる型はこちらを実装し、into_ffi()からはパニックさせる)
+    fn try_into_ffi(self) -> Result<(Self::CType, Self::Guard), FfiError>
+    where
+        Self: Sized,
+    {
+        Ok(self.into_ffi())
+    }
+}
+
+// =====================================================================
+// Cと同じメモリレイアウトを持ち、参照をポインタとして渡せる型を示すマーカー
+// (&T -> *const T、&mut T -> *mut T、#[derive(IntoFfiArg)]、ffi_arg_passthrough!で実装される)
+// =====================================================================
+/// # Safety
+/// 実装する型は#[repr(C)]、#[repr(transparent)]等、C側と同じメモリレイアウトを持つこと
+pub unsafe trait ReprC {}
+
+// =====================================================================
+// Option<T>のNoneとしてnullを渡すためのトレイト(extern宣言側の型がポインタの場合に実装)
+// =====================================================================
+pub trait NullablePointer {
+    fn null() -> Self;
+}
+
+impl<T> NullablePointer for *const T {
+    fn null() -> Self {
+        std::ptr::null()
+    }
+}
+
+impl<T> NullablePointer for *mut T {
+    fn null() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+// =====================================================================
+// そのままextern宣言側に渡す型にIntoFfiArgとReprCを実装するマクロ
+// (#[repr(C)]の構造体を値渡しする場合等に使用する)
+// =====================================================================
+#[macro_export]
+macro_rules! ffi_arg_passthrough {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl $crate::IntoFfiArg for $ty {
+                type CType = $ty;
+                type Guard = ();
+                fn into_ffi(self) -> (Self::CType, Self::Guard) {
+                    (self, ())
+                }
+            }
+
+            unsafe impl $crate::ReprC for $ty {}
+        )*
+    };
+}
+
+ffi_arg_passthrough!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool
+);
+
+impl<T> IntoFfiArg for *const T {
+    type CType = *const T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self, ())
+    }
+}
+
+impl<T> IntoFfiArg for *mut T {
+    type CType = *mut T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self, ())
+    }
+}
+
+// =====================================================================
+// &T -> *const T、&mut T -> *mut T (ReprCを実装した型への参照)
+// =====================================================================
+impl<T: ReprC> IntoFfiArg for &T {
+    type CType = *const T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self, ())
+    }
+}
+
+impl<T: ReprC> IntoFfiArg for &mut T {
+    type CType = *mut T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self, ())
+    }
+}
+
+// =====================================================================
+// Option<T> -> Tのポインタ (Noneの場合はnull)
+// (Option<&S>、Option<&str>等、変換後の型がポインタの場合のみ)
+// =====================================================================
+impl<T> IntoFfiArg for Option<T>
+where
+    T: IntoFfiArg,
+    T::CType: NullablePointer,
+{
+    type CType = T::CType;
+    type Guard = Option<T::Guard>;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        match self {
+            Some(value) => {
+                let (c_value, guard) = value.into_ffi();
+                (c_value, Some(guard))
+            }
+            None => (NullablePointer::null(), None),
+        }
+    }
+
+    fn try_into_ffi(self) -> Result<(Self::CType, Self::Guard), FfiError> {
+        match self {
+            Some(value) => {
+                let (c_value, guard) = value.try_into_ffi()?;
+                Ok((c_value, Some(guard)))
+            }
+            None => Ok((NullablePointer::null(), None)),
+        }
+    }
+}
+
+// =====================================================================
+// 関数ポインタ(Option<関数ポインタ>を含む)はそのまま渡す
+// (ヘッダーのコールバック型のtypedef等、型エイリアスで宣言される場合のため)
+// =====================================================================
+macro_rules! impl_fn_pointer {
+    ($($arg:ident),*) => {
+        impl_fn_pointer!(@abi "C" $(, $arg)*);
+        impl_fn_pointer!(@abi "system" $(, $arg)*);
+    };
+    (@abi $abi:literal $(, $arg:ident)*) => {
+        impl_fn_pointer!(@impl unsafe extern $abi fn($($arg),*) -> R; $($arg),*);
+        impl_fn_pointer!(@impl extern $abi fn($($arg),*) -> R; $($arg),*);
+    };
+    (@impl $fn_ty:ty; $($arg:ident),*) => {
+        impl<R, $($arg),*> IntoFfiArg for $fn_ty {
+            type CType = Self;
+            type Guard = ();
+            fn into_ffi(self) -> (Self::CType, Self::Guard) {
+                (self, ())
+            }
+        }
+
+        impl<R, $($arg),*> IntoFfiArg for Option<$fn_ty> {
+            type CType = Self;
+            type Guard = ();
+            fn into_ffi(self) -> (Self::CType, Self::Guard) {
+                (self, ())
+            }
+        }
+    };
+}
+
+impl_fn_pointer!();
+impl_fn_pointer!(A);
+impl_fn_pointer!(A, B);
+impl_fn_pointer!(A, B, C);
+impl_fn_pointer!(A, B, C, D);
+impl_fn_pointer!(A, B, C, D, E);
+impl_fn_pointer!(A, B, C, D, E, F);
+impl_fn_pointer!(A, B, C, D, E, F, G);
+impl_fn_pointer!(A, B, C, D, E, F, G, H);
+
+// =====================================================================
+// &str,&String,String -> *const c_char (CStringを呼び出しの間保持する)
+// =====================================================================
+impl IntoFfiArg for &str {
+    type CType = *const c_char;
+    type Guard = CString;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        self.try_into_ffi().unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    // 途中にNULを含む文字列は変換できない
+    fn try_into_ffi(self) -> Result<(Self::CType, Self::Guard), FfiError> {
+        let c_string = CString::new(self).map_err(|e| FfiError::Conversion(e.to_string()))?;
+        Ok((c_string.as_ptr(), c_string))
+    }
+}
+
+// =====================================================================
+// &CStr,&CString -> *const c_char (NUL終端済みのためそのまま渡す)
+// =====================================================================
+impl IntoFfiArg for &CStr {
+    type CType = *const c_char;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_ptr(), ())
+    }
+}
+
+impl IntoFfiArg for &CString {
+    type CType = *const c_char;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_ptr(), ())
+    }
+}
+
+impl IntoFfiArg for &String {
+    type CType = *const c_char;
+    type Guard = CString;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        self.as_str().into_ffi()
+    }
+
+    fn try_into_ffi(self) -> Result<(Self::CType, Self::Guard), FfiError> {
+        self.as_str().try_into_ffi()
+    }
+}
+
+impl IntoFfiArg for String {
+    type CType = *const c_char;
+    type Guard = CString;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        self.as_str().into_ffi()
+    }
+
+    fn try_into_ffi(self) -> Result<(Self::CType, Self::Guard), FfiError> {
+        self.as_str().try_into_ffi()
+    }
+}
+
+// =====================================================================
+// &mut String -> *mut c_char
+// (NUL終端のバッファを渡し、呼び出し後に最初のNULまでをStringに書き戻す)
+// (バッファの長さは元の文字列の長さ+1のため、C側でそれを超えて書き込まないこと)
+// =====================================================================
+impl<'a> IntoFfiArg for &'a mut String {
+    type CType = *mut c_char;
+    type Guard = CStringBuffer<'a>;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        let mut buffer = std::mem::take(self).into_bytes();
+        buffer.push(0);
+        let mut guard = CStringBuffer {
+            target: self,
+            buffer,
+        };
+        (guard.buffer.as_mut_ptr().cast(), guard)
+    }
+}
+
+// =====================================================================
+// &mut Stringの書き戻し用ガード
+// =====================================================================
+pub struct CStringBuffer<'a> {
+    target: &'a mut String,
+    buffer: Vec<u8>,
+}
+
+impl Drop for CStringBuffer<'_> {
+    fn drop(&mut self) {
+        let len = self
+            .buffer
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.buffer.len());
+        *self.target = String::from_utf8_lossy(&self.buffer[..len]).into_owned();
+    }
+}
+
+// =====================================================================
+// &[T],&Vec<T>,&[T;N] -> *const T, &mut [T],&mut Vec<T>,&mut [T;N] -> *mut T
+// =====================================================================
+impl<T> IntoFfiArg for &[T] {
+    type CType = *const T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_ptr(), ())
+    }
+}
+
+impl<T> IntoFfiArg for &mut [T] {
+    type CType = *mut T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_mut_ptr(), ())
+    }
+}
+
+impl<T> IntoFfiArg for &Vec<T> {
+    type CType = *const T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_ptr(), ())
+    }
+}
+
+impl<T> IntoFfiArg for &mut Vec<T> {
+    type CType = *mut T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_mut_ptr(), ())
+    }
 }
-// ポインター型の変換用トレイト
-trait ToRawPointer<T>: Sized {
-    fn to_raw_pointer(self) -> RawPointer<T>;
+
+impl<T, const N: usize> IntoFfiArg for &[T; N] {
+    type CType = *const T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_ptr(), ())
+    }
+}
+
+impl<T, const N: usize> IntoFfiArg for &mut [T; N] {
+    type CType = *mut T;
+    type Guard = ();
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_mut_ptr(), ())
+    }
 }
 
-// &Stringでの定数ポインタ変換
-impl<'a> ToRawPointer<u8> for &'a String {
-    fn to_raw_pointer(self) -> RawPointer<u8> {
-        RawPointer::Constant(self.as_ptr())
+// =====================================================================
+// Vec<T>,[T;N] -> *const T (所有権を受け取り、呼び出しの間保持する)
+// ([T;N]はガードの移動でポインタが無効にならないようBoxに移す)
+// =====================================================================
+impl<T> IntoFfiArg for Vec<T> {
+    type CType = *const T;
+    type Guard = Vec<T>;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        (self.as_ptr(), self)
     }
 }
 
-// &mut String での可変ポインタ変換
-impl<'a> ToRawPointer<u8> for &'a mut String {
-    fn to_raw_pointer(self) -> RawPointer<u8> {
-        RawPointer::Mutable(self.as_mut_ptr())
+impl<T, const N: usize> IntoFfiArg for [T; N] {
+    type CType = *const T;
+    type Guard = Box<[T; N]>;
+    fn into_ffi(self) -> (Self::CType, Self::Guard) {
+        let boxed = Box::new(self);
+        (boxed.as_ptr(), boxed)
     }
 }
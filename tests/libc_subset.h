@@ -0,0 +1,62 @@
+/* cffi_gen_from_header!のテスト用ヘッダー(libcの一部) */
+#ifndef CFFI_LIBC_SUBSET_H
+#define CFFI_LIBC_SUBSET_H
+
+#include <stddef.h>
+
+#define CFFI_MAX_PATH 260
+#define CFFI_FLAG_A (1 << 0)
+#define CFFI_FLAG_B (1 << 4)
+#define CFFI_FLAGS (CFFI_FLAG_A | CFFI_FLAG_B)
+#define CFFI_NEGATIVE (-2)
+#define CFFI_BIG 0x100000000LL
+#define CFFI_HUGE (1 << 70)
+#define CFFI_HUGE_NEGATIVE (-CFFI_HUGE)
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct tagCFFI_POINT {
+    int x;
+    int y;
+} CFFI_POINT;
+
+typedef struct {
+    CFFI_POINT origin;
+    char name[CFFI_MAX_PATH];
+    const char *label;
+} CFFI_SHAPE;
+
+typedef struct CFFI_HANDLE_ *CFFI_HANDLE;
+
+enum {
+    CFFI_MODE_NONE,
+    CFFI_MODE_FAST = 3,
+    CFFI_MODE_SLOW,
+};
+
+typedef enum CFFI_COLOR {
+    CFFI_RED = 1,
+    CFFI_GREEN = CFFI_RED << 1,
+} CFFI_COLOR;
+
+typedef int (*CFFI_COMPARE)(const void *, const void *);
+
+int abs(int x);
+long labs(long x);
+size_t strlen(const char *s);
+int __cdecl atoi(const char *nptr);
+void qsort(void *base, size_t nmemb, size_t size, CFFI_COMPARE compar);
+int printf(const char *format, ...);
+int cffi_get_handle(CFFI_HANDLE *handle, CFFI_SHAPE *shape);
+
+static inline int cffi_inline_add(int a, int b) {
+    return a + b;
+}
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif